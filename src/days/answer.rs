@@ -0,0 +1,77 @@
+use std::fmt;
+
+// What a star computed, main is the one printing it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+    // Pixels rendered as lines of text, read by a human
+    Image(String),
+    // The star has no solution yet
+    Unsolved,
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Number(number) => write!(f, "{}", number),
+            Answer::Text(text) => write!(f, "{}", text),
+            Answer::Image(image) => write!(f, "{}", image),
+            Answer::Unsolved => write!(f, "not solved"),
+        }
+    }
+}
+
+impl From<i64> for Answer {
+    fn from(number: i64) -> Self {
+        Answer::Number(number)
+    }
+}
+
+impl From<i32> for Answer {
+    fn from(number: i32) -> Self {
+        Answer::Number(number as i64)
+    }
+}
+
+impl From<u32> for Answer {
+    fn from(number: u32) -> Self {
+        Answer::Number(number as i64)
+    }
+}
+
+impl From<u64> for Answer {
+    fn from(number: u64) -> Self {
+        Answer::Number(number as i64)
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(number: usize) -> Self {
+        Answer::Number(number as i64)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(text: String) -> Self {
+        Answer::Text(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_print_as_they_are() {
+        assert_eq!(Answer::from(-42i32).to_string(), "-42");
+        assert_eq!(Answer::from(3usize), Answer::Number(3));
+        assert_eq!(
+            Answer::from(u64::from(u32::MAX)),
+            Answer::Number(4_294_967_295)
+        );
+        assert_eq!(Answer::from("JKHE".to_string()).to_string(), "JKHE");
+        assert_eq!(Answer::Image("#.\n.#".to_string()).to_string(), "#.\n.#");
+        assert_eq!(Answer::Unsolved.to_string(), "not solved");
+    }
+}
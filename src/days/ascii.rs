@@ -0,0 +1,156 @@
+use std::collections::VecDeque;
+use std::error::Error;
+
+use super::intcode::{CompStatus, InputSource, Intcode};
+
+// Text protocol on top of a machine: outputs that fit in a byte are text, as day 21 read them,
+// anything else (a score, some damage) is kept apart as a value
+#[derive(Clone, Debug)]
+pub struct AsciiIntcode<I = VecDeque<i64>> {
+    machine: Intcode<I, VecDeque<i64>>,
+    // Printed but not read yet
+    text: String,
+    values: Vec<i64>,
+}
+
+impl<I: InputSource> AsciiIntcode<I> {
+    pub fn new(machine: Intcode<I, VecDeque<i64>>) -> Self {
+        AsciiIntcode {
+            machine,
+            text: String::new(),
+            values: vec![],
+        }
+    }
+
+    pub fn machine(&self) -> &Intcode<I, VecDeque<i64>> {
+        &self.machine
+    }
+
+    pub fn machine_mut(&mut self) -> &mut Intcode<I, VecDeque<i64>> {
+        &mut self.machine
+    }
+
+    pub fn status(&self) -> CompStatus {
+        self.machine.status
+    }
+
+    // Runs until the machine needs an input or stops
+    fn pump(&mut self) -> Result<(), Box<dyn Error + 'static>> {
+        self.machine.run_borrowing();
+        for value in self.machine.get_outputs() {
+            match value {
+                0..=255 => self.text.push(value as u8 as char),
+                _ => self.values.push(value),
+            }
+        }
+
+        match self.machine.status {
+            CompStatus::Error(error) => Err(Box::new(error)),
+            _ => Ok(()),
+        }
+    }
+
+    // Everything printed until the machine needs an input or stops
+    pub fn read_all(&mut self) -> Result<String, Box<dyn Error + 'static>> {
+        self.pump()?;
+        Ok(std::mem::take(&mut self.text))
+    }
+
+    // Text up to and including the prompt, whatever was printed after it is kept for the next read
+    pub fn read_until_prompt(&mut self, prompt: &str) -> Result<String, Box<dyn Error + 'static>> {
+        if !self.text.contains(prompt) {
+            self.pump()?;
+        }
+
+        match self.text.find(prompt) {
+            Some(start) => {
+                let rest = self.text.split_off(start + prompt.len());
+                Ok(std::mem::replace(&mut self.text, rest))
+            }
+            None => Err(format!(
+                "Expected `{}` but the machine stopped ({:?}) after printing:\n{}",
+                prompt, self.machine.status, self.text
+            )
+            .into()),
+        }
+    }
+
+    pub fn take_values(&mut self) -> Vec<i64> {
+        std::mem::take(&mut self.values)
+    }
+}
+
+impl AsciiIntcode {
+    // Queued as ascii codes followed by a newline, the machine reads it on the next read call
+    pub fn send_command(&mut self, command: &str) {
+        for byte in command.bytes() {
+            self.machine.add_input_borrowing(byte as i64);
+        }
+        self.machine.add_input_borrowing(10);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days::assembler::assemble;
+
+    // Prints `P?` then `!` on the next line, echoes a line and ends with a score of 1000
+    const ECHO: &str = "
+        out #80
+        out #63
+        out #10
+        out #33
+loop:   in [char]
+        out [char]
+        eq [char], #10, [done]
+        jz [done], #loop
+        out #1000
+        hlt
+char:   db 0
+done:   db 0
+";
+
+    #[test]
+    fn text_after_the_prompt_is_kept() {
+        let mut machine = AsciiIntcode::new(Intcode::new(assemble(ECHO).unwrap()));
+
+        assert_eq!(machine.read_until_prompt("?").unwrap(), "P?");
+        assert_eq!(machine.read_until_prompt("!").unwrap(), "\n!");
+        assert_eq!(machine.status(), CompStatus::Waiting);
+
+        machine.send_command("ok");
+        assert_eq!(machine.read_all().unwrap(), "ok\n");
+        assert_eq!(machine.take_values(), vec![1000]);
+        assert_eq!(machine.status(), CompStatus::Halted);
+    }
+
+    #[test]
+    fn every_byte_is_text() {
+        let mut machine = AsciiIntcode::new(Intcode::new(vec![
+            104, 65, 104, 200, 104, 255, 104, 256, 104, -1, 99,
+        ]));
+
+        assert_eq!(machine.read_all().unwrap(), "A\u{c8}\u{ff}");
+        assert_eq!(machine.take_values(), vec![256, -1]);
+    }
+
+    #[test]
+    fn a_missing_prompt_is_an_error() {
+        let mut machine = AsciiIntcode::new(Intcode::new(assemble(ECHO).unwrap()));
+
+        assert_eq!(
+            machine
+                .read_until_prompt("Command?")
+                .unwrap_err()
+                .to_string(),
+            "Expected `Command?` but the machine stopped (Waiting) after printing:\nP?\n!"
+        );
+
+        let mut faulty = AsciiIntcode::new(Intcode::new(vec![104, 65, 42]));
+        assert_eq!(
+            faulty.read_all().unwrap_err().to_string(),
+            "unknown opcode 42 at address 2"
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use super::intcode::{Opcode, ParameterMode};
+
+// Accepts the same syntax the disassembler prints:
+//   loop: add [rb+3], #5, [120]   ; comment
+//   0012: jz [101], #loop         ; a leading address column is ignored
+//   table: db 1, 2, 3
+// Immediates are written `#value`, positions `[value]` (or a bare value) and relative `[rb+value]`.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssemblerError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssemblerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AssemblerError {}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Number(i64),
+    Label(String),
+}
+
+#[derive(Debug)]
+enum Statement {
+    Instruction(Opcode, Vec<(ParameterMode, Value)>),
+    Data(Vec<Value>),
+}
+
+impl Statement {
+    fn size(&self) -> usize {
+        match self {
+            Statement::Instruction(_, parameters) => 1 + parameters.len(),
+            Statement::Data(values) => values.len(),
+        }
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+fn parse_value(text: &str, line: usize) -> Result<Value, AssemblerError> {
+    let text = text.trim();
+    if let Ok(number) = text.parse::<i64>() {
+        Ok(Value::Number(number))
+    } else if is_identifier(text) {
+        Ok(Value::Label(text.to_string()))
+    } else {
+        Err(AssemblerError {
+            line,
+            message: format!("invalid value `{}`", text),
+        })
+    }
+}
+
+fn parse_parameter(text: &str, line: usize) -> Result<(ParameterMode, Value), AssemblerError> {
+    let text = text.trim();
+
+    if let Some(immediate) = text.strip_prefix('#') {
+        return Ok((ParameterMode::Immediate, parse_value(immediate, line)?));
+    }
+
+    let inner = if text.starts_with('[') && text.ends_with(']') {
+        text[1..text.len() - 1].trim()
+    } else {
+        text
+    };
+
+    if let Some(offset) = inner.strip_prefix("rb") {
+        let offset = offset.trim().replace(' ', "");
+        let value = if offset.is_empty() {
+            Value::Number(0)
+        } else if let Some(positive) = offset.strip_prefix('+') {
+            parse_value(positive, line)?
+        } else if offset.starts_with('-') {
+            parse_value(&offset, line)?
+        } else {
+            return Err(AssemblerError {
+                line,
+                message: format!("invalid relative parameter `{}`", text),
+            });
+        };
+        return Ok((ParameterMode::Relative, value));
+    }
+
+    Ok((ParameterMode::Position, parse_value(inner, line)?))
+}
+
+fn parse_statement(text: &str, line: usize) -> Result<Statement, AssemblerError> {
+    let (mnemonic, rest) = match text.find(char::is_whitespace) {
+        Some(split) => (&text[..split], text[split..].trim()),
+        None => (text, ""),
+    };
+    let arguments = if rest.is_empty() {
+        vec![]
+    } else {
+        rest.split(',').collect::<Vec<_>>()
+    };
+
+    if mnemonic.eq_ignore_ascii_case("db") {
+        return Ok(Statement::Data(
+            arguments
+                .iter()
+                .map(|argument| parse_value(argument, line))
+                .collect::<Result<_, _>>()?,
+        ));
+    }
+
+    let opcode = Opcode::ALL
+        .iter()
+        .cloned()
+        .find(|opcode| opcode.mnemonic().eq_ignore_ascii_case(mnemonic))
+        .ok_or_else(|| AssemblerError {
+            line,
+            message: format!("unknown mnemonic `{}`", mnemonic),
+        })?;
+
+    if arguments.len() != opcode.parameter_count() {
+        return Err(AssemblerError {
+            line,
+            message: format!(
+                "{} expects {} parameters, got {}",
+                opcode.mnemonic(),
+                opcode.parameter_count(),
+                arguments.len()
+            ),
+        });
+    }
+
+    let parameters = arguments
+        .iter()
+        .map(|argument| parse_parameter(argument, line))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if opcode.writes_memory() && parameters.last().unwrap().0 == ParameterMode::Immediate {
+        return Err(AssemblerError {
+            line,
+            message: format!(
+                "{} can't write to an immediate parameter",
+                opcode.mnemonic()
+            ),
+        });
+    }
+
+    Ok(Statement::Instruction(opcode, parameters))
+}
+
+// Returns the memory image that Intcode::new expects
+pub fn assemble(source: &str) -> Result<Vec<i64>, AssemblerError> {
+    let mut labels: HashMap<String, i64> = HashMap::new();
+    let mut statements = vec![];
+    let mut address = 0;
+
+    // First pass: lay out every statement to know where the labels end up
+    for (line_no, line) in source.lines().enumerate() {
+        let line_no = line_no + 1;
+        let mut text = line.split(';').next().unwrap().trim();
+
+        while let Some(colon) = text.find(':') {
+            let prefix = text[..colon].trim();
+            if prefix.chars().all(|c| c.is_ascii_digit()) && !prefix.is_empty() {
+                // Address column from a disassembler listing
+            } else if is_identifier(prefix) {
+                if labels.insert(prefix.to_string(), address).is_some() {
+                    return Err(AssemblerError {
+                        line: line_no,
+                        message: format!("label `{}` is defined twice", prefix),
+                    });
+                }
+            } else {
+                break;
+            }
+            text = text[colon + 1..].trim();
+        }
+
+        if text.is_empty() {
+            continue;
+        }
+
+        let statement = parse_statement(text, line_no)?;
+        address += statement.size() as i64;
+        statements.push((line_no, statement));
+    }
+
+    // Second pass: encode with every label known
+    let resolve = |value: &Value, line: usize| match value {
+        Value::Number(number) => Ok(*number),
+        Value::Label(label) => labels.get(label).cloned().ok_or_else(|| AssemblerError {
+            line,
+            message: format!("unknown label `{}`", label),
+        }),
+    };
+
+    let mut memory = vec![];
+    for (line, statement) in statements {
+        match statement {
+            Statement::Instruction(opcode, parameters) => {
+                let mut instruction = opcode.code();
+                let mut factor = 100;
+                for (mode, _) in parameters.iter() {
+                    instruction += mode.digit() * factor;
+                    factor *= 10;
+                }
+                memory.push(instruction);
+                for (_, value) in parameters.iter() {
+                    memory.push(resolve(value, line)?);
+                }
+            }
+            Statement::Data(values) => {
+                for value in values.iter() {
+                    memory.push(resolve(value, line)?);
+                }
+            }
+        }
+    }
+
+    Ok(memory)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days::disassembler::disassemble;
+    use crate::days::intcode::Intcode;
+
+    // Sums the inputs it is given, as many as `count` says
+    const SUM: &str = "
+        arb #data
+loop:   in [rb+0]
+        add [rb+0], [total], [total]
+        add [count], #-1, [count]
+        jnz [count], #loop
+        out [total]
+        hlt
+count:  db 3
+total:  db 0
+data:   db 0
+";
+
+    fn round_trip(memory: &[i64]) {
+        assert_eq!(assemble(&disassemble(memory)).unwrap(), *memory);
+    }
+
+    #[test]
+    fn labels_data_and_every_mode() {
+        let memory = assemble(SUM).unwrap();
+
+        assert_eq!(
+            memory,
+            vec![
+                109, 20, 203, 0, 201, 0, 19, 19, 1001, 18, -1, 18, 1005, 18, 2, 4, 19, 99, 3, 0, 0
+            ]
+        );
+        assert_eq!(
+            Intcode::new(memory.clone())
+                .add_input(1)
+                .add_input(2)
+                .add_input(4)
+                .run()
+                .get_outputs(),
+            vec![7]
+        );
+        round_trip(&memory);
+    }
+
+    #[test]
+    fn puzzle_programs_survive_a_round_trip() {
+        // Day 9's quine, then day 5's comparison with 8
+        round_trip(&[
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ]);
+        round_trip(&[
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ]);
+    }
+
+    #[test]
+    fn unknown_mnemonics_and_labels_are_located() {
+        assert_eq!(
+            assemble("add #1, #2, [0]\n  mov #1, [0]").unwrap_err(),
+            AssemblerError {
+                line: 2,
+                message: "unknown mnemonic `mov`".to_string(),
+            }
+        );
+        assert_eq!(
+            assemble("jz #0, #end\nhlt").unwrap_err().to_string(),
+            "line 1: unknown label `end`"
+        );
+    }
+}
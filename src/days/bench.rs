@@ -0,0 +1,159 @@
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+use super::Day;
+
+// Short enough to compare a 1.5ms star with a 1.5s one at a glance
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs_f64();
+    if seconds >= 1.0 {
+        format!("{:.3}s", seconds)
+    } else if seconds >= 0.001 {
+        format!("{:.3}ms", seconds * 1_000.0)
+    } else {
+        format!("{:.1}µs", seconds * 1_000_000.0)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Timing {
+    pub day: u32,
+    pub part: u32,
+    // One duration per measured run, sorted, or why the star failed
+    pub runs: Result<Vec<Duration>, String>,
+}
+
+impl Timing {
+    pub fn failed(&self) -> bool {
+        self.runs.is_err()
+    }
+}
+
+fn timed_runs(
+    day: &Day,
+    part: u32,
+    warmup: usize,
+    iterations: usize,
+) -> Result<Vec<Duration>, Box<dyn Error + 'static>> {
+    for _ in 0..warmup {
+        day.solve(part)?;
+    }
+
+    let mut runs = vec![];
+    for _ in 0..iterations.max(1) {
+        let now = Instant::now();
+        day.solve(part)?;
+        runs.push(now.elapsed());
+    }
+    runs.sort();
+
+    Ok(runs)
+}
+
+// Runs the star `warmup` times without timing it, then `iterations` times, at least once.
+// The first failing run stops the measure.
+pub fn measure(day: &Day, part: u32, warmup: usize, iterations: usize) -> Timing {
+    Timing {
+        day: day.number,
+        part,
+        runs: timed_runs(day, part, warmup, iterations).map_err(|error| error.to_string()),
+    }
+}
+
+pub fn table(timings: &[Timing]) -> String {
+    let mut lines = vec![format!(
+        "{:>3} {:>4} {:>5} {:>10} {:>10} {:>10}",
+        "Day", "Part", "Runs", "Min", "Median", "Max"
+    )];
+    let mut total = Duration::default();
+
+    for timing in timings {
+        match &timing.runs {
+            Ok(runs) => {
+                let median = runs[runs.len() / 2];
+                total += median;
+                lines.push(format!(
+                    "{:>3} {:>4} {:>5} {:>10} {:>10} {:>10}",
+                    timing.day,
+                    timing.part,
+                    runs.len(),
+                    format_duration(runs[0]),
+                    format_duration(median),
+                    format_duration(runs[runs.len() - 1])
+                ));
+            }
+            Err(error) => {
+                lines.push(format!(
+                    "{:>3} {:>4}  ERROR {}",
+                    timing.day, timing.part, error
+                ));
+            }
+        }
+    }
+    lines.push(format!("Total of the medians: {}", format_duration(total)));
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days::answer::Answer;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+    static COUNTED: Day = Day {
+        number: 1,
+        title: "Counted",
+        inputs: &[],
+        first_star: || {
+            RUNS.fetch_add(1, Ordering::SeqCst);
+            Ok(Answer::Number(1))
+        },
+        second_star: Some(|| Err("no input".into())),
+    };
+
+    #[test]
+    fn durations_keep_three_digits() {
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1.500s");
+        assert_eq!(format_duration(Duration::from_micros(1500)), "1.500ms");
+        assert_eq!(format_duration(Duration::from_nanos(1500)), "1.5µs");
+    }
+
+    #[test]
+    fn warmup_runs_are_not_timed() {
+        let timing = measure(&COUNTED, 1, 2, 3);
+
+        assert_eq!(RUNS.load(Ordering::SeqCst), 5);
+        let runs = timing.runs.unwrap();
+        assert_eq!(runs.len(), 3);
+        assert!(runs.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn failures_are_reported_in_the_table() {
+        let timings = vec![
+            Timing {
+                day: 1,
+                part: 1,
+                runs: Ok(vec![
+                    Duration::from_millis(1),
+                    Duration::from_millis(2),
+                    Duration::from_millis(4),
+                ]),
+            },
+            measure(&COUNTED, 2, 0, 0),
+        ];
+
+        assert!(timings[1].failed());
+        assert_eq!(
+            table(&timings),
+            "\
+Day Part  Runs        Min     Median        Max
+  1    1     3    1.000ms    2.000ms    4.000ms
+  1    2  ERROR no input
+Total of the medians: 2.000ms"
+        );
+    }
+}
@@ -1,33 +1,63 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
-
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    println!(
-        "{}",
-        fs::read_to_string(Path::new("./data/day1.txt"))? // Yeah, it mostly depends on where you call the exec, but oh well...
-            .split('\n')
-            .map(|x| (x.trim().parse::<i32>().unwrap_or(0) / 3) - 2)
-            .fold(0, |acc, x| x + acc)
-    );
-    Ok(())
+
+use super::answer::Answer;
+use super::input::read_input;
+
+// Blank lines are skipped, a mass of 0 would still count -2 fuel
+pub fn masses(input: &str) -> impl Iterator<Item = i32> + '_ {
+    input
+        .lines()
+        .filter(|x| !x.trim().is_empty())
+        .map(|x| x.trim().parse::<i32>().unwrap_or(0))
+}
+
+pub fn fuel(mass: i32) -> i32 {
+    mass / 3 - 2
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    println!(
-        "Total fuel needed: {}",
-        fs::read_to_string(Path::new("./data/day1.txt"))?
-            .split('\n')
-            .map(|x| {
-                let mut added_fuel = x.trim().parse::<i32>().unwrap_or(0) / 3 - 2;
-                let mut total_fuel = 0;
-                while added_fuel > 0 {
-                    total_fuel += added_fuel;
-                    added_fuel = added_fuel / 3 - 2;
-                }
-                total_fuel
-            })
-            .fold(0, |acc, x| x + acc)
-    );
-    Ok(())
+// The fuel also needs fuel, until the added mass needs none
+pub fn total_fuel(mass: i32) -> i32 {
+    let mut added_fuel = fuel(mass);
+    let mut total_fuel = 0;
+    while added_fuel > 0 {
+        total_fuel += added_fuel;
+        added_fuel = fuel(added_fuel);
+    }
+    total_fuel
+}
+
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(masses(&read_input("day1")?).map(fuel).sum::<i32>().into())
+}
+
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(masses(&read_input("day1")?)
+        .map(total_fuel)
+        .sum::<i32>()
+        .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuel_examples() {
+        assert_eq!(fuel(12), 2);
+        assert_eq!(fuel(14), 2);
+        assert_eq!(fuel(1969), 654);
+        assert_eq!(fuel(100_756), 33583);
+    }
+
+    #[test]
+    fn total_fuel_examples() {
+        assert_eq!(total_fuel(14), 2);
+        assert_eq!(total_fuel(1969), 966);
+        assert_eq!(total_fuel(100_756), 50346);
+    }
+
+    #[test]
+    fn blank_lines_weigh_nothing() {
+        assert_eq!(masses("12\n\n14\n").map(fuel).sum::<i32>(), 4);
+    }
 }
@@ -1,16 +1,17 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
 use std::cmp::Ordering;
 
+use super::answer::Answer;
+use super::input::read_input;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
-struct Point {
-    x: usize,
-    y: usize,
+pub struct Point {
+    pub x: usize,
+    pub y: usize,
 }
 
-fn prepare_file(input: String) -> Vec<Point> {
+pub fn prepare_file(input: &str) -> Vec<Point> {
     input
         .trim()
         .split('\n')
@@ -25,14 +26,14 @@ fn prepare_file(input: String) -> Vec<Point> {
         })
 }
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let map = prepare_file(fs::read_to_string(Path::new("./data/day10.txt"))?);
+// The asteroid seeing the most other asteroids, with how many it sees
+pub fn best_spot(map: &[Point]) -> (Point, usize) {
     let mut best_detection = 0;
     let mut best_spot = Point { x: 0, y: 0 };
 
-    for asteroid_a in &map {
+    for asteroid_a in map {
         let mut equations = vec![];
-        for asteroid_b in &map {
+        for asteroid_b in map {
             if asteroid_b != asteroid_a {
                 let h = match asteroid_b.x.cmp(&asteroid_a.x) {
                     Ordering::Less => 'l',
@@ -62,9 +63,12 @@ pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
         }
     }
 
-    println!("Max visibility is:{}, on {:?}", best_detection, best_spot);
+    (best_spot, best_detection)
+}
 
-    Ok(())
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let (_, detected) = best_spot(&prepare_file(&read_input("day10")?));
+    Ok(detected.into())
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -78,7 +82,12 @@ struct Line {
 }
 
 impl Line {
-    fn new(origin: Point, destination: Point) -> Self {
+    // There is no line from an asteroid to itself
+    fn new(origin: Point, destination: Point) -> Option<Self> {
+        if origin == destination {
+            return None;
+        }
+
         let x = destination.x as i32 - origin.x as i32;
         let y = destination.y as i32 - origin.y as i32;
         let mut angle = (-y as f32 / ((x.pow(2) + y.pow(2)) as f32).sqrt()).acos();
@@ -87,10 +96,6 @@ impl Line {
             angle = 2.0 * std::f32::consts::PI - angle;
         }
 
-        if angle.is_nan() {
-            println!("{:?}, {:?}", origin, destination);
-        }
-
         // Kinda hacking my way around float as working with angles introduce way to much approximation
         let h = match destination.x.cmp(&origin.x) {
             Ordering::Less => 'l',
@@ -105,34 +110,26 @@ impl Line {
         };
         let equ =
             (destination.y as f32 - origin.y as f32) / (destination.x as f32 - origin.x as f32);
-        Line {
+        Some(Line {
             x,
             y,
             destination,
             angle,
             dist: x.abs() + y.abs(),
             equation: format!("{}{}{}", h, v, equ),
-        }
+        })
     }
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let mut asteroid_maps = prepare_file(fs::read_to_string(Path::new("./data/day10.txt"))?);
-
-    let best_spot = Point { x: 23, y: 19 }; // From previous star
-
-    asteroid_maps = asteroid_maps
-        .into_iter()
-        .filter(|x| *x != best_spot)
-        .collect();
-
+// The laser turns clockwise from up, hitting the first asteroid of each line per rotation
+pub fn vaporized(asteroid_maps: Vec<Point>, best_spot: Point, nth: usize) -> Option<Point> {
     let mut lines: Vec<Line> = asteroid_maps
         .iter()
-        .map(|asteroid| Line::new(best_spot, *asteroid))
+        .filter_map(|asteroid| Line::new(best_spot, *asteroid))
         .collect();
 
     lines.sort_by(|a, b| {
-        if (a.angle - b.angle).abs() < std::f32::EPSILON {
+        if (a.angle - b.angle).abs() < f32::EPSILON {
             a.dist.cmp(&b.dist)
         } else {
             a.angle.partial_cmp(&b.angle).unwrap()
@@ -140,29 +137,85 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
     });
 
     let mut count = 0;
-    let destination;
 
-    loop {
+    while !lines.is_empty() {
         let mut removed = lines.clone();
         removed.dedup_by(|a, b| a.equation == b.equation);
 
-        if count + removed.len() >= 200 {
-            destination = removed[199 - count].destination;
-            break;
+        if count + removed.len() >= nth {
+            return Some(removed[nth - 1 - count].destination);
         } else {
             count += removed.len();
-            lines = lines
-                .into_iter()
-                .filter(|elem| !removed.iter().any(|x| x == elem))
-                .collect();
+            lines.retain(|elem| !removed.iter().any(|x| x == elem));
         }
     }
 
-    println!(
-        "200th destroyed: {:?}, answer is: {}",
-        destination,
-        destination.x * 100 + destination.y
-    );
+    None
+}
+
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let map = prepare_file(&read_input("day10")?);
+    let (station, _) = best_spot(&map);
+    let destination = vaporized(map, station, 200).ok_or("Less than 200 asteroids to vaporize")?;
+
+    Ok((destination.x * 100 + destination.y).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LARGE: &str = ".#..##.###...#######
+##.############..##.
+.#.######.########.#
+.###.#######.####.#.
+#####.##.#.##.###.##
+..#####..#.#########
+####################
+#.####....###.#.#.##
+##.#################
+#####.##.###..####..
+..######..##.#######
+####.##.####...##..#
+.#####..#.######.###
+##...#.##########...
+#.##########.#######
+.####.#.###.###.#.##
+....##.##.###..#####
+.#.#.###########.###
+#.#.#.#####.####.###
+###.##.####.##.#..##
+";
+
+    #[test]
+    fn best_spot_examples() {
+        let small = prepare_file(".#..#\n.....\n#####\n....#\n...##");
+        assert_eq!(best_spot(&small), (Point { x: 3, y: 4 }, 8));
+        assert_eq!(
+            best_spot(&prepare_file(LARGE)),
+            (Point { x: 11, y: 13 }, 210)
+        );
+    }
+
+    #[test]
+    fn vaporization_order() {
+        let map = prepare_file(LARGE);
+        let station = Point { x: 11, y: 13 };
+        assert_eq!(
+            vaporized(map.clone(), station, 1),
+            Some(Point { x: 11, y: 12 })
+        );
+        assert_eq!(
+            vaporized(map.clone(), station, 200),
+            Some(Point { x: 8, y: 2 })
+        );
+        assert_eq!(vaporized(map, station, 300), None);
+    }
 
-    Ok(())
+    #[test]
+    fn no_line_from_an_asteroid_to_itself() {
+        let station = Point { x: 3, y: 4 };
+        assert_eq!(Line::new(station, station), None);
+        assert_eq!(Line::new(station, Point { x: 3, y: 2 }).unwrap().angle, 0.0);
+    }
 }
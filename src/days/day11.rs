@@ -1,13 +1,14 @@
 use std::collections::HashMap;
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
+use super::answer::Answer;
+use super::input::read_input;
 use super::intcode::{CompStatus, Intcode};
+use super::program;
 
 type Coordinate = (i32, i32);
 
-struct Robot {
+pub struct Robot {
     coordinate: Coordinate,
     brain: Intcode,
     directions: Vec<Coordinate>,
@@ -23,7 +24,7 @@ impl Robot {
             map: HashMap::new(),
         }
     }
-    pub fn paint(mut self) -> Self {
+    pub fn paint(mut self) -> Result<Self, Box<dyn Error + 'static>> {
         while self.brain.status != CompStatus::Halted {
             let current_color = if let Some(color) = self.map.get(&self.coordinate) {
                 *color as i64
@@ -31,10 +32,23 @@ impl Robot {
                 0
             };
             self.brain = self.brain.add_input(current_color).run();
-            let (new_color, moving) = (
-                self.brain.outputs.pop().unwrap(),
-                self.brain.outputs.pop().unwrap(),
-            );
+            if let CompStatus::Error(error) = self.brain.status {
+                return Err(Box::new(error));
+            }
+            // Halted or waiting for the next color, a brain that does either without a move is stuck
+            let (new_color, moving) = match (
+                self.brain.outputs.pop_front(),
+                self.brain.outputs.pop_front(),
+            ) {
+                (Some(new_color), Some(moving)) => (new_color, moving),
+                _ => {
+                    return Err(format!(
+                        "Robot stopped at {:?} without painting and moving",
+                        self.coordinate
+                    )
+                    .into())
+                }
+            };
             self.map.insert(self.coordinate, new_color as usize);
 
             let temp = self.directions.iter().cycle();
@@ -48,7 +62,7 @@ impl Robot {
             self.coordinate.0 += self.directions[0].0;
             self.coordinate.1 += self.directions[0].1;
         }
-        self
+        Ok(self)
     }
 
     pub fn to_img(&self) -> String {
@@ -67,7 +81,7 @@ impl Robot {
         for y in 0..y_length {
             let mut line = vec![];
             for x in 0..x_length {
-                let color = *(self.map.get(&(x - min_x, y - min_y)).unwrap_or(&0));
+                let color = *(self.map.get(&(x + min_x, y + min_y)).unwrap_or(&0));
                 line.push(if color == 0 { " " } else { "#" });
             }
             result.push(line.join(""));
@@ -76,28 +90,65 @@ impl Robot {
     }
 }
 
-fn prepare_file(input: String) -> Vec<i64> {
-    input
-        .trim()
-        .split(',')
-        .map(|x| (x.trim().parse::<i64>().unwrap_or(0)))
-        .collect::<Vec<_>>()
+// The starting panel is black (0) or white (1)
+pub fn paint(program: &str, start_color: usize) -> Result<Robot, Box<dyn Error + 'static>> {
+    let mut robot = Robot::new(program::load(program)?);
+    if start_color != 0 {
+        robot.map.insert((0, 0), start_color);
+    }
+    robot.paint()
 }
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let memory = prepare_file(fs::read_to_string(Path::new("./data/day11.txt"))?);
-    let robot = Robot::new(memory).paint();
-    println!("Number of individual panel painted: {}", robot.map.len());
-    Ok(())
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let robot = paint(&read_input("day11")?, 0)?;
+    Ok(robot.map.len().into())
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let memory = prepare_file(fs::read_to_string(Path::new("./data/day11.txt"))?);
-    let mut robot = Robot::new(memory);
-    robot.map.insert((0, 0), 1);
-    robot = robot.paint();
-    println!("Image is: ");
-    println!("{}", robot.to_img());
-    println!("Number of individual panel painted: {}", robot.map.len());
-    Ok(())
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let robot = paint(&read_input("day11")?, 1)?;
+    Ok(Answer::Image(robot.to_img()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reads the current color before each (color, turn) pair, like the real brain
+    fn brain(moves: &[(i64, i64)]) -> String {
+        let mut program = moves
+            .iter()
+            .map(|(color, turn)| format!("3,1000,104,{},104,{}", color, turn))
+            .collect::<Vec<_>>();
+        program.push("99".to_string());
+        program.join(",")
+    }
+
+    #[test]
+    fn painting_example() {
+        let moves = [(1, 0), (0, 0), (1, 0), (1, 0), (0, 1), (1, 0), (1, 0)];
+        let robot = paint(&brain(&moves), 0).unwrap();
+
+        assert_eq!(robot.map.len(), 6);
+        assert_eq!(robot.coordinate, (0, -1));
+        assert_eq!(robot.to_img(), "  #\n  #\n## ");
+    }
+
+    #[test]
+    fn a_stuck_brain_is_an_error() {
+        // Reads a color then halts, or faults, without saying where to go
+        assert_eq!(
+            paint("3,1000,104,1,99", 0).err().unwrap().to_string(),
+            "Robot stopped at (0, 0) without painting and moving"
+        );
+        assert_eq!(
+            paint("3,1000,42", 0).err().unwrap().to_string(),
+            "unknown opcode 42 at address 2"
+        );
+    }
+
+    #[test]
+    fn image_starts_at_the_top_left_panel() {
+        let robot = paint(&brain(&[(1, 0), (1, 0), (0, 0)]), 1).unwrap();
+        assert_eq!(robot.to_img(), "##\n  ");
+    }
 }
@@ -1,23 +1,24 @@
 use std::collections::HashSet;
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
 use itertools::Itertools;
 use regex::Regex;
 
+use super::answer::Answer;
+use super::input::read_input;
+
 #[derive(Debug)]
-struct Moon {
-    x: i64,
-    y: i64,
-    z: i64,
-    vx: i64,
-    vy: i64,
-    vz: i64,
+pub struct Moon {
+    pub x: i64,
+    pub y: i64,
+    pub z: i64,
+    pub vx: i64,
+    pub vy: i64,
+    pub vz: i64,
 }
 
 impl Moon {
-    fn new(x: i64, y: i64, z: i64) -> Self {
+    pub fn new(x: i64, y: i64, z: i64) -> Self {
         Moon {
             x,
             y,
@@ -71,10 +72,10 @@ impl Moon {
     }
 }
 
-fn prepare_file(input: String) -> Vec<Moon> {
+pub fn prepare_file(input: &str) -> Vec<Moon> {
     let numbers = Regex::new(r"<x=(?P<x>-?\d+), y=(?P<y>-?\d+), z=(?P<z>-?\d+)").unwrap();
     let mut moons: Vec<Moon> = vec![];
-    for cap in numbers.captures_iter(&input) {
+    for cap in numbers.captures_iter(input) {
         moons.push(Moon::new(
             cap["x"].parse().unwrap(),
             cap["y"].parse().unwrap(),
@@ -84,14 +85,13 @@ fn prepare_file(input: String) -> Vec<Moon> {
     moons
 }
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let mut moons = prepare_file(fs::read_to_string(Path::new("./data/day12.txt"))?);
+pub fn total_energy(mut moons: Vec<Moon>, steps: usize) -> i64 {
     let mut gravities = vec![];
     for (a, b) in (0..moons.len()).tuple_combinations() {
         gravities.push((a, b));
     }
 
-    for _ in 0..1000 {
+    for _ in 0..steps {
         for (a, b) in gravities.iter() {
             let variation = moons[*a].get_gravity_variation(&moons[*b]);
             moons[*a].apply_gravity_variation(variation);
@@ -103,11 +103,12 @@ pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
         }
     }
 
-    let energy = moons.iter().fold(0, |acc, moon| acc + moon.get_energy());
-
-    println!("Big moon energy! {}", energy);
+    moons.iter().fold(0, |acc, moon| acc + moon.get_energy())
+}
 
-    Ok(())
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let moons = prepare_file(&read_input("day12")?);
+    Ok(total_energy(moons, 1000).into())
 }
 
 fn pgcd(a: i64, b: i64) -> i64 {
@@ -129,8 +130,7 @@ fn ppcm(a: i64, b: i64) -> i64 {
  * And then really hinted by this one: https://www.reddit.com/r/adventofcode/comments/e9r2sz/day12_part_2_totally_stuck_on_how_to_approach_this/
  * (Strangely enough: LCM didn't stroke me as much as "each axes are independant")
  */
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let mut moons = prepare_file(fs::read_to_string(Path::new("./data/day12.txt"))?);
+pub fn cycle_length(mut moons: Vec<Moon>) -> i64 {
     let mut gravities = vec![];
     for (a, b) in (0..moons.len()).tuple_combinations() {
         gravities.push((a, b));
@@ -179,16 +179,37 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
         count += 1;
     }
 
-    let absolute_ppcm = ppcm(ppcm(count_x.unwrap(), count_y.unwrap()), count_z.unwrap());
+    ppcm(ppcm(count_x.unwrap(), count_y.unwrap()), count_z.unwrap())
+}
+
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let moons = prepare_file(&read_input("day12")?);
+    Ok(cycle_length(moons).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    println!(
-        "x loop: {}, y loop: {}, z loop: {}",
-        count_x.unwrap_or(-1),
-        count_y.unwrap_or(-1),
-        count_z.unwrap_or(-1)
-    );
+    const FIRST: &str = "<x=-1, y=0, z=2>
+<x=2, y=-10, z=-7>
+<x=4, y=-8, z=8>
+<x=3, y=5, z=-1>";
 
-    println!("Min time: {}", absolute_ppcm);
+    const SECOND: &str = "<x=-8, y=-10, z=0>
+<x=5, y=5, z=10>
+<x=2, y=-7, z=3>
+<x=9, y=-8, z=-3>";
 
-    Ok(())
+    #[test]
+    fn total_energy_examples() {
+        assert_eq!(total_energy(prepare_file(FIRST), 10), 179);
+        assert_eq!(total_energy(prepare_file(SECOND), 100), 1940);
+    }
+
+    #[test]
+    fn cycle_length_examples() {
+        assert_eq!(cycle_length(prepare_file(FIRST)), 2772);
+        assert_eq!(cycle_length(prepare_file(SECOND)), 4_686_774_924);
+    }
 }
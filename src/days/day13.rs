@@ -1,35 +1,25 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
 use itertools::Itertools;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
-use super::intcode::{CompStatus, Intcode};
+use super::answer::Answer;
+use super::input::read_input;
+use super::intcode::{CompStatus, FnInput, FnOutput, Intcode};
+use super::program::{self, Patch};
 
-fn prepare_file(input: String) -> Vec<i64> {
-    input
-        .trim()
-        .split(',')
-        .map(|x| (x.trim().parse::<i64>().unwrap_or(0)))
-        .collect::<Vec<_>>()
-}
-
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, PartialEq, Eq, Hash)]
 struct Position {
     x: i64,
     y: i64,
 }
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let memory = prepare_file(fs::read_to_string(Path::new("./data/day13.txt"))?);
-    let mut int_machine = Intcode::new(memory).run();
+// Outputs come as (x, y, tile) triplets, a later tile replaces the one drawn before
+pub fn block_count(outputs: Vec<i64>) -> usize {
     let mut map: HashMap<(i64, i64), i64> = HashMap::new();
 
-    let mut outputs = int_machine.get_outputs();
-    outputs.reverse();
-
     for chunk in outputs.into_iter().chunks(3).into_iter() {
         let values = chunk.collect::<Vec<i64>>();
         if values.len() == 3 {
@@ -37,90 +27,120 @@ pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
         }
     }
 
-    let tiles = map
-        .values()
-        .fold(0, |acc, val| if *val == 2 { acc + 1 } else { acc });
-    println!("Blocks? {}", tiles);
-    Ok(())
+    map.values()
+        .fold(0, |acc, val| if *val == 2 { acc + 1 } else { acc })
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let mut memory = prepare_file(fs::read_to_string(Path::new("./data/day13.txt"))?);
-    memory[0] = 2;
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let memory = program::load(&read_input("day13")?)?;
     let mut int_machine = Intcode::new(memory).run();
 
-    // init phase
-    let mut blocks: HashSet<Position> = HashSet::new();
-    let mut ball = Position { x: 0, y: 0 };
-    let mut paddle = Position { x: 0, y: 0 };
-    let mut score = 0;
+    Ok(block_count(int_machine.get_outputs()).into())
+}
 
-    let mut outputs = int_machine.get_outputs();
-    outputs.reverse();
+#[derive(Debug, Default)]
+struct Cabinet {
+    pending: Vec<i64>,
+    blocks: HashSet<Position>,
+    ball: Position,
+    paddle: Position,
+    score: i64,
+}
 
-    for chunk in outputs.into_iter().chunks(3).into_iter() {
-        let values = chunk.collect::<Vec<i64>>();
-        if values.len() == 3 {
-            match (values[0], values[1], values[2]) {
-                (-1, 0, x) => {
-                    score = x;
-                }
-                (x, y, 2) => {
-                    blocks.insert(Position { x, y });
-                }
-                (x, y, 3) => {
-                    paddle.x = x;
-                    paddle.y = y;
-                }
-                (x, y, 4) => {
-                    ball.x = x;
-                    ball.y = y;
-                }
-                _ => {}
+impl Cabinet {
+    fn draw(&mut self, value: i64) {
+        self.pending.push(value);
+        if self.pending.len() < 3 {
+            return;
+        }
+
+        match (self.pending[0], self.pending[1], self.pending[2]) {
+            (-1, 0, x) => {
+                self.score = x;
+            }
+            (x, y, 2) => {
+                self.blocks.insert(Position { x, y });
+            }
+            (x, y, 3) => {
+                self.paddle = Position { x, y };
+            }
+            (x, y, 4) => {
+                self.ball = Position { x, y };
+            }
+            (x, y, _) => {
+                self.blocks.remove(&Position { x, y });
             }
         }
+        self.pending.clear();
     }
 
-    //Update
-    while int_machine.status != CompStatus::Halted && !blocks.is_empty() {
+    fn joystick(&self) -> i64 {
         use std::cmp::Ordering::*;
-        int_machine = int_machine
-            .add_input(match ball.x.cmp(&paddle.x) {
-                Less => -1,
-                Equal => 0,
-                Greater => 1,
-            })
-            .run();
-        let mut outputs = int_machine.get_outputs();
-        outputs.reverse();
-
-        for chunk in outputs.into_iter().chunks(3).into_iter() {
-            let values = chunk.collect::<Vec<i64>>();
-            if values.len() == 3 {
-                match (values[0], values[1], values[2]) {
-                    (-1, 0, x) => {
-                        score = x;
-                    }
-                    (x, y, 3) => {
-                        paddle.x = x;
-                        paddle.y = y;
-                    }
-                    (x, y, 4) => {
-                        ball.x = x;
-                        ball.y = y;
-                    }
-                    (x, y, value) => {
-                        let posi = Position { x, y };
-                        if blocks.contains(&posi) && value == 0 {
-                            blocks.remove(&posi);
-                        }
-                    }
-                }
-            }
+        match self.ball.x.cmp(&self.paddle.x) {
+            Less => -1,
+            Equal => 0,
+            Greater => 1,
         }
     }
+}
+
+// Two quarters in the machine
+const FREE_PLAY: &[Patch] = &[(0, 2)];
+
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let memory = program::load_patched(&read_input("day13")?, FREE_PLAY)?;
+
+    // The cabinet is updated on every output so the joystick always follows the latest ball position,
+    // the game halts by itself once every block is broken
+    let cabinet = RefCell::new(Cabinet::default());
+    let int_machine = Intcode::with_io(
+        memory,
+        FnInput(|| Some(cabinet.borrow().joystick())),
+        FnOutput(|value| cabinet.borrow_mut().draw(value)),
+    )
+    .run();
+
+    if let CompStatus::Error(error) = int_machine.status {
+        return Err(Box::new(error));
+    }
+
+    let score = cabinet.borrow().score;
+    Ok(score.into())
+}
 
-    println!("Total score is: {}", score);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(())
+    fn cabinet(outputs: &[i64]) -> Cabinet {
+        let mut cabinet = Cabinet::default();
+        for value in outputs {
+            cabinet.draw(*value);
+        }
+        cabinet
+    }
+
+    #[test]
+    fn only_blocks_are_counted() {
+        // The example from the puzzle: a paddle and a ball
+        assert_eq!(block_count(vec![1, 2, 3, 6, 5, 4]), 0);
+        assert_eq!(block_count(vec![1, 1, 2, 2, 1, 2, 1, 1, 0, 3, 1, 2]), 2);
+    }
+
+    #[test]
+    fn cabinet_follows_the_outputs() {
+        let cabinet = cabinet(&[4, 2, 2, 5, 2, 2, 3, 9, 3, 5, 8, 4, 5, 2, 0, -1, 0, 12]);
+        let blocks: HashSet<Position> = vec![Position { x: 4, y: 2 }].into_iter().collect();
+        assert_eq!(cabinet.blocks, blocks);
+        assert_eq!(cabinet.paddle, Position { x: 3, y: 9 });
+        assert_eq!(cabinet.ball, Position { x: 5, y: 8 });
+        assert_eq!(cabinet.score, 12);
+    }
+
+    #[test]
+    fn joystick_follows_the_ball() {
+        assert_eq!(cabinet(&[3, 9, 3, 5, 8, 4]).joystick(), 1);
+        assert_eq!(cabinet(&[3, 9, 3, 1, 8, 4]).joystick(), -1);
+        assert_eq!(cabinet(&[3, 9, 3, 3, 8, 4]).joystick(), 0);
+    }
 }
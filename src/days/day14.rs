@@ -1,17 +1,18 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
 use regex::Regex;
 use std::collections::HashMap;
 
+use super::answer::Answer;
+use super::input::read_input;
+
 #[derive(Debug, Clone)]
-struct Recipe {
-    qut_produced: u64,
-    composition: HashMap<String, u64>,
+pub struct Recipe {
+    pub qut_produced: u64,
+    pub composition: HashMap<String, u64>,
 }
 
-fn get_recipes(input: String) -> HashMap<String, Recipe> {
+pub fn get_recipes(input: &str) -> HashMap<String, Recipe> {
     let reg = Regex::new(r"(\d+) (\w+)").unwrap();
     let mut values: HashMap<String, Recipe> = HashMap::new();
 
@@ -44,7 +45,7 @@ fn get_recipes(input: String) -> HashMap<String, Recipe> {
     values
 }
 
-fn run_machine(recipes: &HashMap<String, Recipe>, fuel_to_produce: u64) -> u64 {
+pub fn run_machine(recipes: &HashMap<String, Recipe>, fuel_to_produce: u64) -> u64 {
     let mut status = recipes.get("FUEL").unwrap().composition.clone();
     let mut left_overs: HashMap<String, u64> = HashMap::new();
 
@@ -89,21 +90,9 @@ fn run_machine(recipes: &HashMap<String, Recipe>, fuel_to_produce: u64) -> u64 {
     ore_count
 }
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let recipes = get_recipes(fs::read_to_string(Path::new("./data/day14.txt"))?);
-    println!(
-        "Minimum quantity of ORE required: {}",
-        run_machine(&recipes, 1)
-    );
-
-    Ok(())
-}
-
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let available_ore: u64 = 1_000_000_000_000;
-    let recipes = get_recipes(fs::read_to_string(Path::new("./data/day14.txt"))?);
-
-    let fuel_for_ore = run_machine(&recipes, 1);
+// Grows the amount of fuel by what the remaining ore would make at the single fuel rate
+pub fn max_fuel(recipes: &HashMap<String, Recipe>, available_ore: u64) -> u64 {
+    let fuel_for_ore = run_machine(recipes, 1);
 
     let mut attempt = 0;
     let mut left_overs_ore = available_ore;
@@ -115,7 +104,7 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
             left_overs_ore / fuel_for_ore
         };
         attempt += fuel_to_add;
-        let ore_consumed = run_machine(&recipes, attempt);
+        let ore_consumed = run_machine(recipes, attempt);
 
         if ore_consumed < available_ore {
             left_overs_ore = available_ore - ore_consumed;
@@ -125,6 +114,79 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
         }
     }
 
-    println!("Maximum fuel: {}", attempt);
-    Ok(())
+    attempt
+}
+
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let recipes = get_recipes(&read_input("day14")?);
+    Ok(run_machine(&recipes, 1).into())
+}
+
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let recipes = get_recipes(&read_input("day14")?);
+    Ok(max_fuel(&recipes, 1_000_000_000_000).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRILLION: u64 = 1_000_000_000_000;
+
+    const SMALL: &str = "10 ORE => 10 A
+1 ORE => 1 B
+7 A, 1 B => 1 C
+7 A, 1 C => 1 D
+7 A, 1 D => 1 E
+7 A, 1 E => 1 FUEL";
+
+    const SHARED: &str = "9 ORE => 2 A
+8 ORE => 3 B
+7 ORE => 5 C
+3 A, 4 B => 1 AB
+5 B, 7 C => 1 BC
+4 C, 1 A => 1 CA
+2 AB, 3 BC, 4 CA => 1 FUEL";
+
+    const MEDIUM: &str = "157 ORE => 5 NZVS
+165 ORE => 6 DCFZ
+44 XJWVT, 5 KHKGT, 1 QDVJ, 29 NZVS, 9 GPVTF, 48 HKGWZ => 1 FUEL
+12 HKGWZ, 1 GPVTF, 8 PSHF => 9 QDVJ
+179 ORE => 7 PSHF
+177 ORE => 5 HKGWZ
+7 DCFZ, 7 PSHF => 2 XJWVT
+165 ORE => 2 GPVTF
+3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT";
+
+    const LARGE: &str = "171 ORE => 8 CNZTR
+7 ZLQW, 3 BMBT, 9 XCVML, 26 XMNCP, 1 WPTQ, 2 MZWV, 1 RJRHP => 4 PLWSL
+114 ORE => 4 BHXH
+14 VRPVC => 6 BMBT
+6 BHXH, 18 KTJDG, 12 WPTQ, 7 PLWSL, 31 FHTLT, 37 ZDVW => 1 FUEL
+6 WPTQ, 2 BMBT, 8 ZLQW, 18 KTJDG, 1 XMNCP, 6 MZWV, 1 RJRHP => 6 FHTLT
+15 XDBXC, 2 LTCX, 1 VRPVC => 6 ZLQW
+13 WPTQ, 10 LTCX, 3 RJRHP, 14 XMNCP, 2 MZWV, 1 ZLQW => 1 ZDVW
+5 BMBT => 4 WPTQ
+189 ORE => 9 KTJDG
+1 MZWV, 17 XDBXC, 3 XCVML => 2 XMNCP
+12 VRPVC, 27 CNZTR => 2 XDBXC
+15 KTJDG, 12 BHXH => 5 XCVML
+3 BHXH, 2 VRPVC => 7 MZWV
+121 ORE => 7 VRPVC
+7 XCVML => 6 RJRHP
+5 BHXH, 4 VRPVC => 5 LTCX";
+
+    #[test]
+    fn ore_for_one_fuel() {
+        assert_eq!(run_machine(&get_recipes(SMALL), 1), 31);
+        assert_eq!(run_machine(&get_recipes(SHARED), 1), 165);
+        assert_eq!(run_machine(&get_recipes(MEDIUM), 1), 13312);
+        assert_eq!(run_machine(&get_recipes(LARGE), 1), 2_210_736);
+    }
+
+    #[test]
+    fn fuel_for_a_trillion_ore() {
+        assert_eq!(max_fuel(&get_recipes(MEDIUM), TRILLION), 82_892_753);
+        assert_eq!(max_fuel(&get_recipes(LARGE), TRILLION), 460_664);
+    }
 }
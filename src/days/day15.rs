@@ -1,13 +1,15 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
 use std::collections::HashMap;
 
+use super::answer::Answer;
+use super::grid::{self, Coordinate};
+use super::input::read_input;
 use super::intcode;
+use super::program;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-enum Tile {
+pub enum Tile {
     Empty(usize),
     Wall,
     Oxygen(usize),
@@ -15,10 +17,9 @@ enum Tile {
     Start,
 }
 
-type Coordinate = (i32, i32);
-type Map = HashMap<Coordinate, Tile>;
+pub type Map = HashMap<Coordinate, Tile>;
 
-fn explore_map(mut repair_drone: intcode::Intcode) -> (Map, Tile, Coordinate) {
+pub fn explore_map(mut repair_drone: intcode::Intcode) -> (Map, Tile, Coordinate) {
     use self::Tile::*;
 
     let mut position = (0, 0);
@@ -49,10 +50,8 @@ fn explore_map(mut repair_drone: intcode::Intcode) -> (Map, Tile, Coordinate) {
             let next_tile = *(area.entry(next_pos).or_insert(Unknown));
             match next_tile {
                 Unknown => available_step.push((*next_step, next_pos)),
-                Oxygen(recorded_dist) | Empty(recorded_dist) => {
-                    if recorded_dist > dist + 1 {
-                        available_step.push((*next_step, next_pos));
-                    }
+                Oxygen(recorded_dist) | Empty(recorded_dist) if recorded_dist > dist + 1 => {
+                    available_step.push((*next_step, next_pos));
                 }
                 _ => {}
             }
@@ -106,117 +105,64 @@ fn explore_map(mut repair_drone: intcode::Intcode) -> (Map, Tile, Coordinate) {
     (area, oxygen_tile, oxygen_position.unwrap())
 }
 
-fn draw_map(area: &Map) {
-    use Tile::*;
-    let mut min_x: Option<i32> = None;
-    let mut min_y: Option<i32> = None;
-    let mut max_x: Option<i32> = None;
-    let mut max_y: Option<i32> = None;
-    for (x, y) in area.keys() {
-        min_x = if let Some(min_x) = min_x {
-            Some(std::cmp::min(*x, min_x))
-        } else {
-            Some(*x)
-        };
-
-        min_y = if let Some(min_y) = min_y {
-            Some(std::cmp::min(*y, min_y))
-        } else {
-            Some(*y)
-        };
-
-        max_x = if let Some(max_x) = max_x {
-            Some(std::cmp::max(*x, max_x))
-        } else {
-            Some(*x)
-        };
-
-        max_y = if let Some(max_y) = max_y {
-            Some(std::cmp::max(*y, max_y))
-        } else {
-            Some(*y)
-        };
-    }
-
-    for y in min_y.unwrap()..=max_y.unwrap() {
-        for x in min_x.unwrap()..=max_x.unwrap() {
-            let tile = area.get(&(x, y)).unwrap_or(&Unknown);
-            print!(
-                "{}",
-                match tile {
-                    Empty(_) => '.',
-                    Wall => '#',
-                    Oxygen(_) => 'O',
-                    Unknown => '?',
-                    Start => 'X',
-                }
-            );
-        }
-        println!();
-    }
-}
-
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let (area, oxygen_tile, _) = explore_map(intcode::Intcode::new_with_path(fs::read_to_string(
-        Path::new("./data/day15.txt"),
-    )?));
-    draw_map(&area);
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let (_, oxygen_tile, _) =
+        explore_map(intcode::Intcode::new(program::load(&read_input("day15")?)?));
 
-    if let Tile::Oxygen(dist) = oxygen_tile {
-        println!("Oxygen found at dist: {}", dist);
+    match oxygen_tile {
+        Tile::Oxygen(dist) => Ok(dist.into()),
+        _ => Err("The droid never found the oxygen system".into()),
     }
-
-    Ok(())
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
+// Minutes for the oxygen to reach every empty tile of the area from `position`
+pub fn fill_time(area: Map, position: Coordinate) -> usize {
     use Tile::*;
-    let (mut area, _, position) = explore_map(intcode::Intcode::new_with_path(fs::read_to_string(
-        Path::new("./data/day15.txt"),
-    )?));
-
-    area.insert(position, Oxygen(0));
-
-    let mut to_explore = vec![position];
-    let moves = vec![(0, 1), (0, -1), (1, 0), (-1, 0)];
-    let mut max_dist = 0;
-
-    while !to_explore.is_empty() {
-        let oxy_position = to_explore.pop().unwrap();
-
-        let dist = if let Oxygen(dist_to_origin) = area.get(&oxy_position).unwrap() {
-            *dist_to_origin
-        } else {
-            0
-        };
+    grid::distances(position, |coordinate| {
+        matches!(
+            area.get(&coordinate),
+            Some(Empty(_)) | Some(Oxygen(_)) | Some(Start)
+        )
+    })
+    .values()
+    .max()
+    .copied()
+    .unwrap_or(0)
+}
 
-        if dist > max_dist {
-            max_dist = dist;
-        }
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let (area, _, position) =
+        explore_map(intcode::Intcode::new(program::load(&read_input("day15")?)?));
+    Ok(fill_time(area, position).into())
+}
 
-        for movement in moves.iter() {
-            let neighbor = (oxy_position.0 + movement.0, oxy_position.1 + movement.1);
-            if let Some(empty_cell) = area.get_mut(&neighbor) {
-                *empty_cell = match empty_cell {
-                    Oxygen(previous_dist) => {
-                        if *previous_dist < dist + 1 {
-                            Oxygen(*previous_dist)
-                        } else {
-                            Oxygen(dist + 1)
-                        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_time_example() {
+        let mut area = HashMap::new();
+        let mut oxygen = (0, 0);
+        for (y, line) in [" ##   ", "#..## ", "#.#..#", "#.O.# ", " ###  "]
+            .iter()
+            .enumerate()
+        {
+            for (x, c) in line.chars().enumerate() {
+                let position = (x as i32, y as i32);
+                match c {
+                    '#' => {
+                        area.insert(position, Tile::Wall);
                     }
-                    Empty(_) => {
-                        to_explore.push(neighbor);
-                        Oxygen(dist + 1)
+                    '.' => {
+                        area.insert(position, Tile::Empty(0));
                     }
-                    Unknown => Unknown,
-                    Start => Oxygen(dist + 1),
-                    Wall => Wall,
+                    'O' => oxygen = position,
+                    _ => {}
                 }
             }
         }
-    }
 
-    println!("It takes {} mins to fully fill the area", max_dist);
-    Ok(())
+        assert_eq!(fill_time(area, oxygen), 4);
+    }
 }
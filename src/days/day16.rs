@@ -1,15 +1,17 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let current_input = fs::read_to_string(Path::new("./data/day16.txt"))?;
-    let mut current_input = current_input
+use super::answer::Answer;
+use super::input::read_input;
+
+// The first eight digits of the signal after `phases` phases
+pub fn fft(signal: &str, phases: usize) -> String {
+    let mut current_input = signal
+        .trim()
         .chars()
         .map(|elem| elem.to_digit(10).unwrap() as isize)
         .collect::<Vec<isize>>();
 
-    for _ in 0..100 {
+    for _ in 0..phases {
         let mut next_input = vec![];
 
         for i in 1..=current_input.len() / 2 {
@@ -40,19 +42,20 @@ pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
         current_input = next_input;
     }
 
-    let result = current_input
+    current_input
         .iter()
         .take(8)
-        .fold(String::new(), |acc, elem| acc + &elem.to_string());
+        .fold(String::new(), |acc, elem| acc + &elem.to_string())
+}
 
-    println!("{}", result);
-    Ok(())
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(Answer::Text(fft(&read_input("day16")?, 100)))
 }
 
 // Solution HEAVILY influenced by this Reddit Thread: https://www.reddit.com/r/adventofcode/comments/ebf5cy/2019_day_16_part_2_understanding_how_to_come_up/
 // Also remember using partial sum (https://github.com/enjmusic/aoc_2019/blob/master/aoc_16/src/main.rs => apply_fft)
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let input = fs::read_to_string(Path::new("./data/day16.txt"))?;
+pub fn real_message(signal: &str) -> String {
+    let input = signal.trim();
     let message_offset = input
         .chars()
         .take(7)
@@ -89,14 +92,36 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
         input = next_input;
     }
 
-    println!(
-        "{}",
-        input
-            .into_iter()
-            .take(8)
-            .map(|x| x.to_string())
-            .collect::<String>()
-    );
+    input
+        .into_iter()
+        .take(8)
+        .map(|x| x.to_string())
+        .collect::<String>()
+}
 
-    Ok(())
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(Answer::Text(real_message(&read_input("day16")?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fft_examples() {
+        assert_eq!(fft("12345678", 4), "01029498");
+        assert_eq!(fft("80871224585914546619083218645595", 100), "24176176");
+        assert_eq!(fft("19617804207202209144916044189917", 100), "73745418");
+        assert_eq!(fft("69317163492948606335995924319873\n", 100), "52432133");
+    }
+
+    #[test]
+    fn real_message_examples() {
+        assert_eq!(real_message("03036732577212944063491565474664"), "84462026");
+        assert_eq!(real_message("02935109699940807407585447034323"), "78725270");
+        assert_eq!(
+            real_message("03081770884921959731165446850517\n"),
+            "53553731"
+        );
+    }
 }
@@ -1,10 +1,12 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
 use std::collections::HashMap;
 
+use super::answer::Answer;
+use super::ascii::AsciiIntcode;
+use super::input::read_input;
 use super::intcode;
+use super::program::{self, Patch};
 
 type Coordinate = (usize, usize);
 
@@ -45,23 +47,11 @@ enum Tile {
     Start,
 }
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
+// Sum of x * y over the scaffold intersections
+pub fn alignment(ascii_map: &str) -> usize {
     use Tile::*;
-    let mut robot =
-        intcode::Intcode::new_with_path(fs::read_to_string(Path::new("./data/day17.txt"))?);
-
     let mut map: HashMap<Coordinate, Tile> = HashMap::new();
     let mut alignement = 0;
-
-    robot = robot.run();
-
-    let mut output = robot.get_outputs();
-    output.reverse(); // Because (How could I forgot that...) inputs and outputs are store in a fifo order :D
-
-    let ascii_map = output
-        .into_iter()
-        .map(|x| (x as u8) as char)
-        .collect::<String>();
     let ascii_map = ascii_map.trim();
 
     for (line_no, line) in ascii_map.split('\n').enumerate() {
@@ -106,32 +96,12 @@ pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
         }
     }
 
-    println!("Sum of alignement parameters: {}", alignement);
-    Ok(())
+    alignement
 }
 
-fn find_next_prog(path: &str) -> String {
-    let splitted_path = path.split(' ').collect::<Vec<_>>();
-    let mut prog = splitted_path[0..4].join(" ");
-    let mut step = 4;
-    let mut previous_count = 0;
-
-    loop {
-        let next_prog = vec![prog.clone(), splitted_path[step..step + 2].join(" ")].join(" ");
-        let control_check = splitted_path[step - 2..step + 2].join(" ");
-        let count = path.matches(&next_prog).count();
-        let check_count = path.matches(&control_check).count();
-        if previous_count > count || check_count != count {
-            break;
-        } else {
-            previous_count = count;
-        }
-
-        step += 2;
-        prog = next_prog;
-    }
-
-    prog
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let mut robot = AsciiIntcode::new(intcode::Intcode::new(program::load(&read_input("day17")?)?));
+    Ok(alignment(&robot.read_all()?).into())
 }
 
 fn create_path(map: HashMap<Coordinate, Tile>, mut robot_on_map: Robot) -> String {
@@ -178,23 +148,10 @@ fn create_path(map: HashMap<Coordinate, Tile>, mut robot_on_map: Robot) -> Strin
     path
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
+// The whole path of the robot, from its start to the end of the scaffold
+pub fn scaffold_path(ascii_map: &str) -> String {
     use Tile::*;
-    let mut memory = intcode::prepare_memory(fs::read_to_string(Path::new("./data/day17.txt"))?);
-    memory[0] = 2;
-    let mut robot = intcode::Intcode::new(memory);
-
     let mut map: HashMap<Coordinate, Tile> = HashMap::new();
-
-    robot = robot.run();
-
-    let mut output = robot.get_outputs();
-    output.reverse(); // Because (How could I forgot that...) inputs and outputs are store in a fifo order :D
-
-    let ascii_map = output
-        .into_iter()
-        .map(|x| (x as u8) as char)
-        .collect::<String>();
     let ascii_map = ascii_map.trim();
 
     let mut robot_on_map = Robot::new();
@@ -256,39 +213,176 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
         }
     }
 
-    let path = create_path(map, robot_on_map);
+    create_path(map, robot_on_map)
+}
+
+// The robot memory holds 20 characters per routine, commas included
+const ROUTINE_LENGTH: usize = 20;
+const FUNCTIONS: [&str; 3] = ["A", "B", "C"];
+
+fn fits(moves: &[&str]) -> bool {
+    moves.join(",").len() <= ROUTINE_LENGTH
+}
+
+// Covers the remaining moves with the known functions, or a new one while there are less than three
+fn compress<'a>(
+    moves: &[&'a str],
+    functions: &mut Vec<Vec<&'a str>>,
+    main: &mut Vec<&'static str>,
+) -> bool {
+    if moves.is_empty() {
+        return true;
+    }
+    if main.len() * 2 + 1 > ROUTINE_LENGTH {
+        return false;
+    }
+
+    for index in 0..functions.len() {
+        if moves.starts_with(&functions[index]) {
+            let length = functions[index].len();
+            main.push(FUNCTIONS[index]);
+            if compress(&moves[length..], functions, main) {
+                return true;
+            }
+            main.pop();
+        }
+    }
+
+    if functions.len() < FUNCTIONS.len() {
+        for length in 1..=moves.len() {
+            if !fits(&moves[..length]) {
+                break;
+            }
+            functions.push(moves[..length].to_vec());
+            main.push(FUNCTIONS[functions.len() - 1]);
+            if compress(&moves[length..], functions, main) {
+                return true;
+            }
+            main.pop();
+            functions.pop();
+        }
+    }
 
-    let mut temp_prog = path.replace(",", " ");
+    false
+}
 
-    let prog_a = find_next_prog(&temp_prog);
-    temp_prog = temp_prog.replace(&prog_a, "");
-    let prog_b = find_next_prog(&temp_prog);
-    temp_prog = temp_prog.replace(&prog_b, "");
-    let prog_c = find_next_prog(&temp_prog);
+// The main routine then the A, B and C functions, made of the repeated parts of the path
+pub fn movement_routines(path: &str) -> Option<[String; 4]> {
+    // A turn and its distance are never split between two functions
+    let moves = path
+        .split(',')
+        .collect::<Vec<_>>()
+        .chunks(2)
+        .map(|step| step.join(","))
+        .collect::<Vec<_>>();
+    let moves = moves.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let mut functions = vec![];
+    let mut main = vec![];
+    if !compress(&moves, &mut functions, &mut main) {
+        return None;
+    }
 
-    let prog_a = prog_a.trim().replace(" ", ",");
-    let prog_b = prog_b.trim().replace(" ", ",");
-    let prog_c = prog_c.trim().replace(" ", ",");
+    let mut routines = [main.join(","), String::new(), String::new(), String::new()];
+    for (index, function) in functions.iter().enumerate() {
+        routines[index + 1] = function.join(",");
+    }
+    Some(routines)
+}
+
+// The robot waits for its movement routines instead of only printing the camera view
+const WAKE_UP: &[Patch] = &[(0, 2)];
 
-    let mut final_path = path.replace(&prog_a, "A");
-    final_path = final_path.replace(&prog_b, "B");
-    final_path = final_path.replace(&prog_c, "C");
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let memory = program::load_patched(&read_input("day17")?, WAKE_UP)?;
+    let mut robot = AsciiIntcode::new(intcode::Intcode::new(memory));
 
-    let main_func = format!("{}\n{}\n{}\n{}\nn\n", final_path, prog_a, prog_b, prog_c)
-        .chars()
-        .map(|x| (x as u8) as i64)
-        .collect::<Vec<i64>>();
+    let ascii_map = robot.read_until_prompt("Main:")?;
+    let path = scaffold_path(ascii_map.trim_end_matches("Main:"));
 
-    for i in main_func {
-        robot = robot.add_input(i);
+    let routines = movement_routines(&path).ok_or("The path doesn't fit in three functions")?;
+    for command in routines.iter() {
+        robot.send_command(command);
     }
+    // No live video feed
+    robot.send_command("n");
+    robot.read_all()?;
+
+    let result = robot
+        .take_values()
+        .pop()
+        .ok_or("The robot didn't report the collected dust")?;
 
-    robot = robot.run();
+    Ok(result.into())
+}
 
-    let mut output = robot.get_outputs();
-    output.reverse();
-    let result = output.pop().unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDING: &str = "#######...#####
+#.....#...#...#
+#.....#...#...#
+......#...#...#
+......#...###.#
+......#.....#.#
+^########...#.#
+......#.#...#.#
+......#########
+........#...#..
+....#########..
+....#...#......
+....#...#......
+....#...#......
+....#####......";
+
+    #[test]
+    fn alignment_example() {
+        let map = "..#..........
+..#..........
+#######...###
+#.#...#...#.#
+#############
+..#...#...#..
+..#####...^..";
+        assert_eq!(alignment(map), 76);
+    }
 
-    println!("Result: {}", result);
-    Ok(())
+    #[test]
+    fn path_example() {
+        assert_eq!(
+            scaffold_path(WINDING),
+            "R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2"
+        );
+    }
+
+    #[test]
+    fn routines_rebuild_the_path() {
+        let path = scaffold_path(WINDING);
+        let [main, a, b, c] = movement_routines(&path).unwrap();
+
+        let rebuilt = main
+            .split(',')
+            .map(|function| match function {
+                "A" => a.as_str(),
+                "B" => b.as_str(),
+                "C" => c.as_str(),
+                other => panic!("Unexpected function {}", other),
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        assert_eq!(rebuilt, path);
+        for routine in &[main, a, b, c] {
+            assert!(routine.len() <= 20, "{} is too long", routine);
+        }
+    }
+
+    #[test]
+    fn unique_moves_cannot_be_compressed() {
+        let path = (1..=30)
+            .map(|distance| format!("R,{}", distance))
+            .collect::<Vec<_>>()
+            .join(",");
+        assert_eq!(movement_routines(&path), None);
+    }
 }
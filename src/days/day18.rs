@@ -1,14 +1,11 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
 
-type Coordinate = (usize, usize);
 type Map = HashMap<Coordinate, Tile>;
-type Graph = HashMap<char, Node>;
+pub type Graph = HashMap<char, Node>;
 
 #[derive(Debug, PartialEq)]
 enum Tile {
@@ -21,12 +18,17 @@ enum Tile {
 
 use Tile::*;
 
+use super::answer::Answer;
+use super::grid::{self, Coordinate};
+use super::input::read_input;
+
+// A key, or an entrance named by a digit. `lockers` are the keys needed to reach it from the entrance.
 #[derive(Debug)]
-struct Node {
-    lockers: HashSet<char>,
-    keys: HashSet<char>,
-    is_key: bool,
-    neighbours: HashMap<char, usize>,
+pub struct Node {
+    pub lockers: HashSet<char>,
+    pub keys: HashSet<char>,
+    pub is_key: bool,
+    pub neighbours: HashMap<char, usize>,
 }
 
 impl Node {
@@ -51,13 +53,13 @@ impl Clone for Node {
     }
 }
 
-fn create_map(input: String) -> Map {
-    let mut network: HashMap<(usize, usize), Tile> = HashMap::new();
+fn create_map(input: &str) -> Map {
+    let mut network: Map = HashMap::new();
     let mut start_count = 0;
     for (line_no, line) in input.split('\n').enumerate() {
         let line = line.trim();
         for (col_no, character) in line.chars().enumerate() {
-            let coordinate = (col_no, line_no);
+            let coordinate = (col_no as i32, line_no as i32);
             let tile = match character {
                 '.' => Empty,
                 '#' => Wall,
@@ -92,8 +94,6 @@ fn bfs_from_node(map: &Map, start: Coordinate, is_root: bool, graph: &mut Graph)
 
     let mut lock_states = vec![String::new()];
 
-    let movements = vec![(1, 0), (-1, 0), (0, 1), (0, -1)];
-
     let node_id = match map.get(&start).unwrap() {
         Start(id) | Key(id) => *id,
         _ => unreachable!(),
@@ -109,12 +109,7 @@ fn bfs_from_node(map: &Map, start: Coordinate, is_root: bool, graph: &mut Graph)
 
         distance += 1;
 
-        for movement in movements.iter() {
-            let neighbour_tile = (
-                (coordinate.0 as isize + movement.0) as usize,
-                (coordinate.1 as isize + movement.1) as usize,
-            );
-
+        for neighbour_tile in grid::neighbours(coordinate) {
             if !already_discovered.insert(neighbour_tile) {
                 continue;
             }
@@ -124,7 +119,7 @@ fn bfs_from_node(map: &Map, start: Coordinate, is_root: bool, graph: &mut Graph)
                     node.neighbours.insert(*id, distance);
 
                     if is_root {
-                        let mut remote_node = graph.entry(*id).or_insert_with(Node::new);
+                        let remote_node = graph.entry(*id).or_insert_with(Node::new);
                         remote_node.lockers =
                             HashSet::from_iter(lock_states[lock_state_id].chars());
                     }
@@ -156,29 +151,25 @@ fn bfs_from_node(map: &Map, start: Coordinate, is_root: bool, graph: &mut Graph)
 }
 
 fn bfs_graph_to_star(graph: &Graph) -> usize {
-    type QueuedData<'a> = VecDeque<(Vec<&'a Node>, HashSet<char>, usize, Option<char>)>;
+    // Where each robot stands, the keys collected so far and the distance walked
+    type QueuedData = VecDeque<(Vec<char>, HashSet<char>, usize)>;
 
     let mut min = None;
 
     let mut dedup: HashMap<String, usize> = HashMap::new();
 
-    let mut root_keys = HashSet::new();
-
-    let roots: Vec<&Node> = graph
-        .iter()
-        .filter(|(key, _)| key.is_ascii_digit())
-        .map(|(root_key, node)| {
-            root_keys.insert(*root_key);
-            node
-        })
+    let mut roots: Vec<char> = graph
+        .keys()
+        .filter(|key| key.is_ascii_digit())
+        .copied()
         .collect();
+    roots.sort();
+    let root_keys: HashSet<char> = roots.iter().copied().collect();
 
     let mut queue: QueuedData = VecDeque::new();
 
-    queue.push_front((roots, root_keys, 0, None));
-    while !queue.is_empty() {
-        let (nodes, keys_aquired, dist, last_visited) = queue.pop_front().unwrap();
-
+    queue.push_front((roots, root_keys, 0));
+    while let Some((positions, keys_aquired, dist)) = queue.pop_front() {
         if keys_aquired.len() == graph.len() {
             min = if let Some(min_value) = min {
                 Some(std::cmp::min(min_value, dist))
@@ -187,32 +178,34 @@ fn bfs_graph_to_star(graph: &Graph) -> usize {
             }
         }
 
-        // Dedup algorithm doesn't work on some edge case (the last test case doesn't have consistent result), thankfully, my puzzle input wasn't one of them.
-        if let Some(last_char) = last_visited {
-            let mut signature = keys_aquired.iter().copied().collect::<Vec<char>>();
-            signature.sort();
-            let signature = format!("{}{}", signature.iter().collect::<String>(), last_char);
-
-            if let Some(dedup_min) = dedup.get(&signature) {
-                if *dedup_min <= dist {
-                    continue;
-                }
+        // Two states are the same when the same keys were collected and every robot stands at the same place
+        let mut signature = keys_aquired.iter().copied().collect::<Vec<char>>();
+        signature.sort();
+        let signature = format!(
+            "{}|{}",
+            signature.iter().collect::<String>(),
+            positions.iter().collect::<String>()
+        );
+
+        if let Some(dedup_min) = dedup.get(&signature) {
+            if *dedup_min <= dist {
+                continue;
             }
-
-            dedup.insert(signature, dist);
         }
 
-        for (index, node) in nodes.iter().enumerate() {
+        dedup.insert(signature, dist);
+
+        for (index, position) in positions.iter().enumerate() {
+            let node = graph.get(position).unwrap();
             for (node_name, node_dist) in node.neighbours.iter() {
                 let next_node = graph.get(node_name).unwrap();
                 if next_node.lockers.is_subset(&keys_aquired) && !keys_aquired.contains(node_name) {
-                    let mut other_nodes = nodes.clone();
+                    let mut other_positions = positions.clone();
                     let mut new_keys_aquired = keys_aquired.clone();
                     new_keys_aquired.insert(*node_name);
 
-                    let next_dist = dist + node_dist;
-                    other_nodes[index] = next_node;
-                    queue.push_back((other_nodes, new_keys_aquired, next_dist, Some(*node_name)));
+                    other_positions[index] = *node_name;
+                    queue.push_back((other_positions, new_keys_aquired, dist + node_dist));
                 }
             }
         }
@@ -221,14 +214,15 @@ fn bfs_graph_to_star(graph: &Graph) -> usize {
     min.unwrap_or(0)
 }
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let map = create_map(fs::read_to_string(Path::new("./data/day18_a.txt"))?);
+// Distances between the entrances and the keys, with the doors standing in the way
+pub fn key_graph(input: &str) -> Graph {
+    let map = create_map(input);
     let mut graph = Graph::new();
 
-    for (coord, tile) in map.iter().filter(|(_, tile)| match tile {
-        Key(_) | Start(_) => true,
-        _ => false,
-    }) {
+    for (coord, tile) in map
+        .iter()
+        .filter(|(_, tile)| matches!(tile, Key(_) | Start(_)))
+    {
         let is_root = match tile {
             Start(_) => true,
             Key(_) => false,
@@ -237,28 +231,115 @@ pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
         bfs_from_node(&map, *coord, is_root, &mut graph);
     }
 
-    println!("Min dist: {}", bfs_graph_to_star(&graph));
+    graph
+}
 
-    Ok(())
+// Steps to collect every key, one robot moving at a time when the vault has several entrances
+pub fn shortest_path(input: &str) -> usize {
+    bfs_graph_to_star(&key_graph(input))
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let map = create_map(fs::read_to_string(Path::new("./data/day18_b.txt"))?);
-    let mut graph = Graph::new();
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(shortest_path(&read_input("day18_a")?).into())
+}
 
-    for (coord, tile) in map.iter().filter(|(_, tile)| match tile {
-        Key(_) | Start(_) => true,
-        _ => false,
-    }) {
-        let is_root = match tile {
-            Start(_) => true,
-            Key(_) => false,
-            _ => unreachable!(),
-        };
-        bfs_from_node(&map, *coord, is_root, &mut graph);
-    }
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(shortest_path(&read_input("day18_b")?).into())
+}
 
-    println!("Min dist: {}", bfs_graph_to_star(&graph));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_entrance_examples() {
+        let examples = [
+            ("#########\n#b.A.@.a#\n#########", 8),
+            (
+                "########################
+#f.D.E.e.C.b.A.@.a.B.c.#
+######################.#
+#d.....................#
+########################",
+                86,
+            ),
+            (
+                "########################
+#...............b.C.D.f#
+#.######################
+#.....@.a.B.c.d.A.e.F.g#
+########################",
+                132,
+            ),
+            (
+                "#################
+#i.G..c...e..H.p#
+########.########
+#j.A..b...f..D.o#
+########@########
+#k.E..a...g..B.n#
+########.########
+#l.F..d...h..C.m#
+#################",
+                136,
+            ),
+            (
+                "########################
+#@..............ac.GI.b#
+###d#e#f################
+###A#B#C################
+###g#h#i################
+########################",
+                81,
+            ),
+        ];
+        for (vault, steps) in examples.iter() {
+            assert_eq!(shortest_path(vault), *steps, "{}", vault);
+        }
+    }
 
-    Ok(())
+    #[test]
+    fn four_entrances_examples() {
+        let examples = [
+            (
+                "#######\n#a.#Cd#\n##@#@##\n#######\n##@#@##\n#cB#Ab#\n#######",
+                8,
+            ),
+            (
+                "###############
+#d.ABC.#.....a#
+######@#@######
+###############
+######@#@######
+#b.....#.....c#
+###############",
+                24,
+            ),
+            (
+                "#############
+#DcBa.#.GhKl#
+#.###@#@#I###
+#e#d#####j#k#
+###C#@#@###J#
+#fEbA.#.FgHi#
+#############",
+                32,
+            ),
+            (
+                "#############
+#g#f.D#..h#l#
+#F###e#E###.#
+#dCba@#@BcIJ#
+#############
+#nK.L@#@G...#
+#M###N#H###.#
+#o#m..#i#jk.#
+#############",
+                72,
+            ),
+        ];
+        for (vault, steps) in examples.iter() {
+            assert_eq!(shortest_path(vault), *steps, "{}", vault);
+        }
+    }
 }
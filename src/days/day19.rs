@@ -1,58 +1,73 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
+use super::answer::Answer;
+use super::input::read_input;
 use super::intcode;
+use super::program;
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let mut count = 0;
+// A probe answers in a few hundred instructions, a drone still busy after this went astray
+const PROBE_BUDGET: u64 = 100_000;
+
+pub fn probe(original: &intcode::Intcode, x: i64, y: i64) -> Result<i64, Box<dyn Error + 'static>> {
+    let mut drone = original.clone().add_input(x).add_input(y).run();
+    match drone.status {
+        intcode::CompStatus::Error(error) => Err(Box::new(error)),
+        intcode::CompStatus::OutOfBudget => Err(format!(
+            "Drone probing ({}, {}) is still running after {} steps",
+            x,
+            y,
+            drone.steps()
+        )
+        .into()),
+        _ => drone.get_outputs().first().copied().ok_or_else(|| {
+            format!("Drone probing ({}, {}) stopped without an answer", x, y).into()
+        }),
+    }
+}
 
-    let original =
-        intcode::Intcode::new_with_path(fs::read_to_string(Path::new("./data/day19.txt"))?);
+pub fn drone(program: &str) -> Result<intcode::Intcode, Box<dyn Error + 'static>> {
+    let mut original = intcode::Intcode::new(program::load(program)?);
+    original.enable_decode_cache();
+    original.set_budget(Some(PROBE_BUDGET));
+    Ok(original)
+}
 
-    for x in 0..50 {
-        for y in 0..50 {
-            let mut drone = original.clone();
-            drone = drone.add_input(x).add_input(y).run();
-            let is_tracted = drone.get_outputs()[0];
+// `pulled` tells whether a point is in the tractor beam
+pub fn affected_points<F>(size: i64, mut pulled: F) -> Result<usize, Box<dyn Error + 'static>>
+where
+    F: FnMut(i64, i64) -> Result<bool, Box<dyn Error + 'static>>,
+{
+    let mut count = 0;
 
-            if is_tracted == 1 {
+    for x in 0..size {
+        for y in 0..size {
+            if pulled(x, y)? {
                 count += 1;
             }
         }
     }
 
-    println!("Tracted in {} zones", count);
-    Ok(())
+    Ok(count)
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    const OFFSET: i64 = 99;
-
-    let mut y = OFFSET;
+// Top left corner of the first `size` wide square fitting in the beam, rows are scanned by their
+// bottom left corner and the square fits when its top right corner is pulled too
+pub fn closest_square<F>(size: i64, mut pulled: F) -> Result<(i64, i64), Box<dyn Error + 'static>>
+where
+    F: FnMut(i64, i64) -> Result<bool, Box<dyn Error + 'static>>,
+{
+    let offset = size - 1;
+    let mut y = offset;
     let mut min_x = 0;
 
-    let original =
-        intcode::Intcode::new_with_path(fs::read_to_string(Path::new("./data/day19.txt"))?);
-
-    'outer: loop {
+    loop {
         for x in min_x.. {
-            let mut drone = original.clone();
-            drone = drone.add_input(x).add_input(y).run();
-
-            let mut is_tracted = drone.get_outputs()[0];
-
-            if is_tracted == 1 {
-                let mut drone = original.clone();
-                drone = drone.add_input(x + OFFSET).add_input(y - OFFSET).run();
-                is_tracted = drone.get_outputs()[0];
-            } else {
+            if !pulled(x, y)? {
                 continue;
             }
 
-            if is_tracted == 1 {
-                println!("Result is: {}", x * 10_000 + (y - OFFSET));
-                break 'outer;
+            if pulled(x + offset, y - offset)? {
+                return Ok((x, y - offset));
             } else {
                 y += 1;
                 min_x = std::cmp::max(0, x - 5);
@@ -60,6 +75,67 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
             }
         }
     }
+}
+
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let original = drone(&read_input("day19")?)?;
+    let count = affected_points(50, |x, y| Ok(probe(&original, x, y)? == 1))?;
+
+    Ok(count.into())
+}
 
-    Ok(())
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let original = drone(&read_input("day19")?)?;
+    let (x, y) = closest_square(100, |x, y| Ok(probe(&original, x, y)? == 1))?;
+
+    Ok((x * 10_000 + y).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn affected_points_example() {
+        let beam = [
+            "#.........",
+            ".#........",
+            "..##......",
+            "...###....",
+            "....###...",
+            ".....####.",
+            "......####",
+            "......####",
+            ".......###",
+            "........##",
+        ];
+        let pulled = |x: i64, y: i64| Ok(beam[y as usize].as_bytes()[x as usize] == b'#');
+
+        assert_eq!(affected_points(10, pulled).unwrap(), 27);
+    }
+
+    #[test]
+    fn closest_square_in_a_cone() {
+        // Every x between y / 2 and 2 * y is pulled
+        let pulled = |x: i64, y: i64| Ok(y <= 2 * x && x <= 2 * y);
+
+        assert_eq!(closest_square(10, pulled).unwrap(), (9, 9));
+    }
+
+    #[test]
+    fn probe_errors_are_reported() {
+        let result = closest_square(10, |_, _| Err("The drone is lost".into()));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn a_silent_drone_is_an_error() {
+        // Reads both coordinates then halts
+        let silent = intcode::Intcode::new(vec![3, 0, 3, 0, 99]);
+
+        assert_eq!(
+            probe(&silent, 3, 4).unwrap_err().to_string(),
+            "Drone probing (3, 4) stopped without an answer"
+        );
+    }
 }
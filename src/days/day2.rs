@@ -1,91 +1,107 @@
+use std::convert::TryFrom;
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
-fn prepare_file(input: String) -> Vec<usize> {
-    input
-        .trim()
-        .split(',')
-        .map(|x| (x.trim().parse::<usize>().unwrap_or(0)))
-        .collect::<Vec<_>>()
-}
+use super::answer::Answer;
+use super::input::read_input;
+use super::program;
+
+// The two parameters of the first instruction
+const NOUN: usize = 1;
+const VERB: usize = 2;
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let mut input = prepare_file(fs::read_to_string(Path::new("./data/day2.txt"))?);
-    input[1] = 12;
-    input[2] = 2;
+// Runs the program in place until it halts, only additions and multiplications exist yet
+pub fn execute(memory: &mut [i64]) -> Result<(), Box<dyn Error + 'static>> {
     let mut index = 0;
     loop {
-        match input[index] {
-            1 => {
-                let store_index = input[index + 3];
-                let first_index = input[index + 1];
-                let second_index = input[index + 2];
-                input[store_index] = input[first_index] + input[second_index];
-            }
-            2 => {
-                let store_index = input[index + 3];
-                let first_index = input[index + 1];
-                let second_index = input[index + 2];
-                input[store_index] = input[first_index] * input[second_index];
-            }
-            99 => {
-                println!("Program halted, result at position 0: {}", input[0]);
-                break;
+        match memory.get(index) {
+            Some(1) | Some(2) => {
+                let mut addresses = [0; 3];
+                for (offset, address) in addresses.iter_mut().enumerate() {
+                    *address = memory
+                        .get(index + offset + 1)
+                        .and_then(|&value| usize::try_from(value).ok())
+                        .filter(|&value| value < memory.len())
+                        .ok_or_else(|| {
+                            format!("Invalid address in the instruction at {}", index)
+                        })?;
+                }
+                let [first_index, second_index, store_index] = addresses;
+                memory[store_index] = if memory[index] == 1 {
+                    memory[first_index] + memory[second_index]
+                } else {
+                    memory[first_index] * memory[second_index]
+                };
             }
-            _ => panic!("HALT AND CATCH FIRE"),
+            Some(99) => return Ok(()),
+            _ => return Err(format!("HALT AND CATCH FIRE at {}", index).into()),
         }
         index += 4;
     }
-    Ok(())
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let memory = prepare_file(fs::read_to_string(Path::new("./data/day2.txt"))?);
+pub fn run_with(memory: &[i64], noun: i64, verb: i64) -> Result<i64, Box<dyn Error + 'static>> {
+    let mut memory = memory.to_vec();
+    program::patch(&mut memory, &[(NOUN, noun), (VERB, verb)]);
+    execute(&mut memory)?;
+    Ok(memory[0])
+}
 
-    'outer: for i in 0..100 {
-        for j in 0..100 {
-            let mut input = memory.clone();
-            input[1] = i;
-            input[2] = j;
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let memory = program::load(&read_input("day2")?)?;
+    Ok(run_with(&memory, 12, 2)?.into())
+}
 
-            let mut index = 0;
-            loop {
-                match input[index] {
-                    1 => {
-                        let store_index = input[index + 3];
-                        let first_index = input[index + 1];
-                        let second_index = input[index + 2];
-                        input[store_index] = input[first_index] + input[second_index];
-                    }
-                    2 => {
-                        let store_index = input[index + 3];
-                        let first_index = input[index + 1];
-                        let second_index = input[index + 2];
-                        input[store_index] = input[first_index] * input[second_index];
-                    }
-                    99 => {
-                        break;
-                    }
-                    _ => {
-                        println!("HALT AND CATCH FIRE");
-                        break;
-                    }
-                }
-                index += 4;
-            }
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let memory = program::load(&read_input("day2")?)?;
 
-            if input[0] == 19_690_720 {
-                println!(
-                    "Noun: {}, verb: {}, answer: {}",
-                    input[1],
-                    input[2],
-                    100 * input[1] + input[2]
-                );
-                break 'outer;
+    for noun in 0..100 {
+        for verb in 0..100 {
+            // A pair crashing the program is just not the right one
+            if let Ok(19_690_720) = run_with(&memory, noun, verb) {
+                return Ok((100 * noun + verb).into());
             }
         }
     }
 
-    Ok(())
+    Err("No noun and verb give 19690720".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn executed(text: &str) -> Vec<i64> {
+        let mut memory = program::load(text).unwrap();
+        execute(&mut memory).unwrap();
+        memory
+    }
+
+    #[test]
+    fn examples() {
+        assert_eq!(
+            executed("1,9,10,3,2,3,11,0,99,30,40,50"),
+            vec![3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50]
+        );
+        assert_eq!(executed("1,0,0,0,99"), vec![2, 0, 0, 0, 99]);
+        assert_eq!(executed("2,3,0,3,99"), vec![2, 3, 0, 6, 99]);
+        assert_eq!(executed("2,4,4,5,99,0"), vec![2, 4, 4, 5, 99, 9801]);
+        assert_eq!(
+            executed("1,1,1,4,99,5,6,0,99"),
+            vec![30, 1, 1, 4, 2, 5, 6, 0, 99]
+        );
+    }
+
+    #[test]
+    fn crashes_are_errors() {
+        assert!(execute(&mut program::load("3,0,0,0,99").unwrap()).is_err());
+        assert!(execute(&mut program::load("1,0,-1,0,99").unwrap()).is_err());
+    }
+
+    #[test]
+    fn noun_and_verb_replace_the_first_parameters() {
+        assert_eq!(
+            run_with(&program::load("1,0,0,0,99").unwrap(), 4, 4).unwrap(),
+            198
+        );
+    }
 }
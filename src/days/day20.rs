@@ -1,11 +1,12 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
 
+use super::answer::Answer;
+use super::input::read_input;
+
 type Coordinate = (usize, usize);
 
 type CharMaze = Vec<Vec<char>>;
@@ -80,7 +81,7 @@ fn find_portal(
     }
 }
 
-fn prepare_file(input: String) -> (Maze, Warps, Coordinate, Coordinate) {
+fn prepare_file(input: &str) -> (Maze, Warps, Coordinate, Coordinate) {
     use self::Tile::*;
 
     let mut partial_gate_list: HashSet<Coordinate> = HashSet::new();
@@ -143,13 +144,12 @@ fn prepare_file(input: String) -> (Maze, Warps, Coordinate, Coordinate) {
     )
 }
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
+pub fn shortest_path(input: &str) -> Option<usize> {
     use self::Tile::*;
 
-    let (maze, warps, start_point, end_point) =
-        prepare_file(fs::read_to_string(Path::new("./data/day20.txt"))?);
+    let (maze, warps, start_point, end_point) = prepare_file(input);
 
-    let directions = vec![(-1, 0), (1, 0), (0, -1), (0, 1)];
+    let directions = [(-1, 0), (1, 0), (0, -1), (0, 1)];
     let mut already_explored: HashSet<Coordinate> = HashSet::new();
     let mut to_explore = VecDeque::new();
 
@@ -163,8 +163,7 @@ pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
         }
 
         if current_coordinate == end_point {
-            println!("Total dist from AA to ZZ: {}", dist);
-            break;
+            return Some(dist);
         }
 
         for direction in directions.iter() {
@@ -187,16 +186,19 @@ pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
             }
         }
     }
-    Ok(())
+
+    None
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
+// Inner portals lead one level down, outer ones one level up, ZZ is only open on the outermost level
+pub fn recursive_path(input: &str) -> Option<usize> {
     use self::Tile::*;
 
-    let (maze, warps, start_point, end_point) =
-        prepare_file(fs::read_to_string(Path::new("./data/day20.txt"))?);
+    let (maze, warps, start_point, end_point) = prepare_file(input);
+    // Going deeper than there are portals never comes back up in time, a maze with no way out stops there
+    let max_depth = warps.len();
 
-    let directions = vec![(-1, 0), (1, 0), (0, -1), (0, 1)];
+    let directions = [(-1, 0), (1, 0), (0, -1), (0, 1)];
     let mut already_explored: HashSet<(Coordinate, usize)> = HashSet::new();
     let mut to_explore = VecDeque::new();
 
@@ -210,8 +212,7 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
         }
 
         if current_coordinate == end_point && depth == 0 {
-            println!("Total dist from AA to ZZ: {}", dist);
-            break;
+            return Some(dist);
         }
 
         for direction in directions.iter() {
@@ -228,7 +229,7 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
                     if warps.contains_key(&current_coordinate) {
                         let (coordinate, is_inner_ring) = *warps.get(&current_coordinate).unwrap();
 
-                        if depth == 0 && !is_inner_ring {
+                        if (depth == 0 && !is_inner_ring) || (is_inner_ring && depth == max_depth) {
                             continue;
                         }
 
@@ -241,5 +242,99 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
             }
         }
     }
-    Ok(())
+
+    None
+}
+
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(shortest_path(&read_input("day20")?)
+        .ok_or("ZZ can't be reached from AA")?
+        .into())
+}
+
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(recursive_path(&read_input("day20")?)
+        .ok_or("ZZ can't be reached from AA")?
+        .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Built line by line, the trailing spaces are part of the maze
+    const SMALL: &str = concat!(
+        "         A           \n",
+        "         A           \n",
+        "  #######.#########  \n",
+        "  #######.........#  \n",
+        "  #######.#######.#  \n",
+        "  #######.#######.#  \n",
+        "  #######.#######.#  \n",
+        "  #####  B    ###.#  \n",
+        "BC...##  C    ###.#  \n",
+        "  ##.##       ###.#  \n",
+        "  ##...DE  F  ###.#  \n",
+        "  #####    G  ###.#  \n",
+        "  #########.#####.#  \n",
+        "DE..#######...###.#  \n",
+        "  #.#########.###.#  \n",
+        "FG..#########.....#  \n",
+        "  ###########.#####  \n",
+        "             Z       \n",
+        "             Z       \n",
+    );
+
+    const LARGE: &str = concat!(
+        "                   A               \n",
+        "                   A               \n",
+        "  #################.#############  \n",
+        "  #.#...#...................#.#.#  \n",
+        "  #.#.#.###.###.###.#########.#.#  \n",
+        "  #.#.#.......#...#.....#.#.#...#  \n",
+        "  #.#########.###.#####.#.#.###.#  \n",
+        "  #.............#.#.....#.......#  \n",
+        "  ###.###########.###.#.#.#.#.###  \n",
+        "  #.....#        A   C    #.#.#.#  \n",
+        "  #######        S   P    #####.#  \n",
+        "  #.#...#                 #......VT\n",
+        "  #.#.#.#                 #.#####  \n",
+        "  #...#.#               YN....#.#  \n",
+        "  #.###.#                 #####.#  \n",
+        "DI....#.#                 #.....#  \n",
+        "  #####.#                 #.###.#  \n",
+        "ZZ......#               QG....#..AS\n",
+        "  ###.###                 #######  \n",
+        "JO..#.#.#                 #.....#  \n",
+        "  #.#.#.#                 ###.#.#  \n",
+        "  #...#..DI             BU....#..LF\n",
+        "  #####.#                 #.#####  \n",
+        "YN......#               VT..#....QG\n",
+        "  #.###.#                 #.###.#  \n",
+        "  #.#...#                 #.....#  \n",
+        "  ###.###    J L     J    #.#.###  \n",
+        "  #.....#    O F     P    #.#...#  \n",
+        "  #.###.#####.#.#####.#####.###.#  \n",
+        "  #...#.#.#...#.....#.....#.#...#  \n",
+        "  #.#####.###.###.#.#.#########.#  \n",
+        "  #...#.#.....#...#.#.#.#.....#.#  \n",
+        "  #.###.#####.###.###.#.#.#######  \n",
+        "  #.#.........#...#.............#  \n",
+        "  #########.###.###.#############  \n",
+        "           B   J   C               \n",
+        "           U   P   P               \n",
+    );
+
+    #[test]
+    fn shortest_path_examples() {
+        assert_eq!(shortest_path(SMALL), Some(23));
+        assert_eq!(shortest_path(LARGE), Some(58));
+    }
+
+    #[test]
+    fn recursive_path_examples() {
+        assert_eq!(recursive_path(SMALL), Some(26));
+        // Every way down keeps going deeper
+        assert_eq!(recursive_path(LARGE), None);
+    }
 }
@@ -1,84 +1,72 @@
+use std::collections::VecDeque;
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
+use super::answer::Answer;
+use super::ascii::AsciiIntcode;
+use super::input::read_input;
 use super::intcode;
-
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let mut jumping_drone =
-        intcode::Intcode::new_with_path(fs::read_to_string(Path::new("./data/day21.txt"))?);
-    let program = "OR A T\nAND B T\nAND C T\nNOT T J\nAND D J\nWALK\n";
-
-    let jmp_prog = program
-        .chars()
-        .map(|x| (x as u8) as i64)
-        .collect::<Vec<i64>>();
-
-    jumping_drone = jumping_drone.run();
-
-    jumping_drone.get_outputs();
-
-    for i in jmp_prog {
-        jumping_drone = jumping_drone.add_input(i);
+use super::program;
+
+// The droid describes how it fell, or only tells the hull damage when it made it
+pub fn report<I: intcode::InputSource>(
+    drone: &mut AsciiIntcode<I>,
+) -> Result<Answer, Box<dyn Error + 'static>> {
+    let text = drone.read_all()?;
+    match drone.take_values().pop() {
+        Some(damage) => Ok(damage.into()),
+        None => Err(format!("The droid didn't make it:\n{}", text).into()),
     }
+}
 
-    jumping_drone = jumping_drone.run();
-
-    let mut output = jumping_drone.get_outputs();
-
-    output.reverse();
-
-    let text = output.iter().fold(String::new(), |mut acc, x| {
-        if *x < 256 {
-            let character = (*x as u8) as char;
-            acc.push(character);
-        } else {
-            return format!("{}{}", acc, x);
-        }
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let memory = program::load(&read_input("day21")?)?;
+    let program = "OR A T\nAND B T\nAND C T\nNOT T J\nAND D J\nWALK\n";
 
-        acc
-    });
+    // The springscript is fed line by line as the droid asks for it
+    let mut jumping_drone = AsciiIntcode::new(intcode::Intcode::with_io(
+        memory,
+        intcode::AsciiInput::new(program.as_bytes()),
+        VecDeque::new(),
+    ));
 
-    println!("Result:\n{}", text);
-    Ok(())
+    report(&mut jumping_drone)
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let mut jumping_drone =
-        intcode::Intcode::new_with_path(fs::read_to_string(Path::new("./data/day21.txt"))?);
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let memory = program::load(&read_input("day21")?)?;
     let program =
         "OR A T\nAND B T\nAND C T\nNOT T J\nAND D J\nNOT E T\nNOT T T\nOR H T\nAND T J\nRUN\n";
 
-    let jmp_prog = program
-        .chars()
-        .map(|x| (x as u8) as i64)
-        .collect::<Vec<i64>>();
+    // The springscript is fed line by line as the droid asks for it
+    let mut jumping_drone = AsciiIntcode::new(intcode::Intcode::with_io(
+        memory,
+        intcode::AsciiInput::new(program.as_bytes()),
+        VecDeque::new(),
+    ));
 
-    jumping_drone = jumping_drone.run();
+    report(&mut jumping_drone)
+}
 
-    jumping_drone.get_outputs();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for i in jmp_prog {
-        jumping_drone = jumping_drone.add_input(i);
+    fn droid(program: &str) -> AsciiIntcode<VecDeque<i64>> {
+        AsciiIntcode::new(intcode::Intcode::new(program::load(program).unwrap()))
     }
 
-    jumping_drone = jumping_drone.run();
-
-    let mut output = jumping_drone.get_outputs();
-
-    output.reverse();
-
-    let text = output.iter().fold(String::new(), |mut acc, x| {
-        if *x < 256 {
-            let character = (*x as u8) as char;
-            acc.push(character);
-        } else {
-            return format!("{}{}", acc, x);
-        }
-
-        acc
-    });
+    #[test]
+    fn hull_damage_is_the_answer() {
+        // Prints "ok" then a value too large to be a character
+        assert_eq!(
+            report(&mut droid("104,111,104,107,104,10,104,19354437,99")).unwrap(),
+            Answer::Number(19_354_437)
+        );
+    }
 
-    println!("Result:\n{}", text);
-    Ok(())
+    #[test]
+    fn a_fall_is_an_error_with_its_description() {
+        let error = report(&mut droid("104,35,104,10,99")).unwrap_err();
+        assert!(error.to_string().contains("#"));
+    }
 }
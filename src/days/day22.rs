@@ -1,11 +1,13 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
-fn prepare_file(input: String, stack_len: i128) -> (i128, i128) {
+use super::answer::Answer;
+use super::input::read_input;
+
+// The shuffle as a linear function: the card at position p ends up at coef.0 * p + coef.1
+fn prepare_file(input: &str, stack_len: i128) -> (i128, i128) {
     let mut coef = (1, 0);
 
-    for line in input.lines() {
+    for line in input.lines().filter(|line| !line.trim().is_empty()) {
         let splitted: Vec<String> = line.split(' ').map(|elem| elem.to_string()).collect();
         let attempt_parsing = splitted.last().unwrap().parse::<i128>();
 
@@ -30,7 +32,7 @@ fn prepare_file(input: String, stack_len: i128) -> (i128, i128) {
 
 // Copied from https://www.csee.umbc.edu/~chang/cs203.s09/exteuclid.shtml, needed http://defeo.lu/in310/poly/euclide-bezout/ and https://www.mathraining.be/chapters/4?type=1&which=16 to understand it
 // Inverse modulo is located in the 3 tuple
-fn extended_euclid(max: i128, min: i128) -> (i128, i128, i128) {
+pub fn extended_euclid(max: i128, min: i128) -> (i128, i128, i128) {
     if min == 0 {
         (max, 1, 0)
     } else {
@@ -40,7 +42,7 @@ fn extended_euclid(max: i128, min: i128) -> (i128, i128, i128) {
 }
 
 // https://en.wikipedia.org/wiki/Exponentiation_by_squaring, provided me wrong results because of the way I initialy handled odd numbered powers, couldn't have guessed without code from here https://github.com/enjmusic/aoc_2019/blob/master/aoc_22/src/main.rs
-fn modular_power(base: i128, power: i128, modulo: i128) -> i128 {
+pub fn modular_power(base: i128, power: i128, modulo: i128) -> i128 {
     let mut total = base % modulo;
     let mut current_pow = power;
     let mut temp = 1;
@@ -56,34 +58,27 @@ fn modular_power(base: i128, power: i128, modulo: i128) -> i128 {
     (total * temp) % modulo
 }
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let stack_len = 10_007;
-    let coef = prepare_file(
-        fs::read_to_string(Path::new("./data/day22.txt"))?,
-        stack_len,
-    );
+// Where `card` ends up after one shuffle
+pub fn position(shuffle: &str, stack_len: i128, card: i128) -> i128 {
+    let coef = prepare_file(shuffle, stack_len);
 
-    let mut result = (2019 * coef.0 + coef.1) % stack_len;
+    let mut result = (card * coef.0 + coef.1) % stack_len;
 
     if result < 0 {
         result += stack_len;
     }
 
-    println!("From position 2019 to {}", result);
-
-    Ok(())
+    result
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let stack_len = 119_315_717_514_047_i128;
-    let num_of_computations = 101_741_582_076_661_i128;
+// Which card is at `position` after shuffling `num_of_computations` times, the deck size must be prime
+pub fn card_at(shuffle: &str, stack_len: i128, num_of_computations: i128, position: i128) -> i128 {
+    let (factor, constant) = prepare_file(shuffle, stack_len);
+    // Kept positive, extended_euclid gives a negated inverse when its gcd comes out as -1
+    let (factor, constant) = (factor.rem_euclid(stack_len), constant.rem_euclid(stack_len));
 
-    let (factor, constant) = prepare_file(
-        fs::read_to_string(Path::new("./data/day22.txt"))?,
-        stack_len,
-    );
-
-    let (_, _, denominator_inverse) = extended_euclid(stack_len, 1 - factor);
+    let (_, _, denominator_inverse) =
+        extended_euclid(stack_len, (1 - factor).rem_euclid(stack_len));
 
     let total_multiplier = modular_power(factor, num_of_computations, stack_len);
 
@@ -94,14 +89,84 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
 
     let (_, _, reverse_multiplier) = extended_euclid(stack_len, total_multiplier); // Note: According to solution, I could also find any reverse by calculing a^b where b is stack_len - 2, since stack_len is prime, any a^(stack_len-1) = 1 mod stack_len
 
-    let mut result = (2020 - total_offset) % stack_len;
+    let mut result = (position - total_offset) % stack_len;
     result = (result * reverse_multiplier) % stack_len;
 
     if result < 0 {
         result += stack_len;
     }
 
-    println!("{}", result);
+    result
+}
+
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let result = position(&read_input("day22")?, 10_007, 2019);
+
+    // Both positions are reduced modulo the deck size, they fit in an i64
+    Ok((result as i64).into())
+}
+
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let result = card_at(
+        &read_input("day22")?,
+        119_315_717_514_047,
+        101_741_582_076_661,
+        2020,
+    );
 
-    Ok(())
+    Ok((result as i64).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deck(shuffle: &str) -> Vec<i128> {
+        let mut deck = vec![0; 10];
+        for card in 0..10 {
+            deck[position(shuffle, 10, card) as usize] = card;
+        }
+        deck
+    }
+
+    #[test]
+    fn small_deck_examples() {
+        let examples = [
+            (
+                "deal with increment 7\ndeal into new stack\ndeal into new stack",
+                [0, 3, 6, 9, 2, 5, 8, 1, 4, 7],
+            ),
+            (
+                "cut 6\ndeal with increment 7\ndeal into new stack",
+                [3, 0, 7, 4, 1, 8, 5, 2, 9, 6],
+            ),
+            (
+                "deal with increment 7\ndeal with increment 9\ncut -2",
+                [6, 3, 0, 7, 4, 1, 8, 5, 2, 9],
+            ),
+            (
+                "deal into new stack\ncut -2\ndeal with increment 7\ncut 8\ncut -4\ndeal with increment 7\ncut 3\ndeal with increment 9\ndeal with increment 3\ncut -1\n",
+                [9, 2, 5, 8, 1, 4, 7, 0, 3, 6],
+            ),
+        ];
+        for (shuffle, expected) in examples.iter() {
+            assert_eq!(deck(shuffle), expected.to_vec(), "{}", shuffle);
+        }
+    }
+
+    #[test]
+    fn card_at_undoes_repeated_shuffles() {
+        let shuffles = [
+            "deal into new stack\ncut -2\ndeal with increment 7\ncut 8\ncut -4",
+            "cut 6\ndeal with increment 7\ndeal into new stack",
+            "deal with increment 7\ndeal with increment 9\ncut -2",
+        ];
+        for shuffle in shuffles.iter() {
+            let mut card_position = 2019;
+            for times in 1..=5 {
+                card_position = position(shuffle, 10_007, card_position);
+                assert_eq!(card_at(shuffle, 10_007, times, card_position), 2019);
+            }
+        }
+    }
 }
@@ -1,164 +1,144 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
-use std::collections::VecDeque;
-
-use super::intcode;
-
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let nic = fs::read_to_string(Path::new("./data/day23.txt"))?;
+use super::answer::Answer;
+use super::input::read_input;
+use super::intcode::CompStatus;
+use super::network::{AddressRouter, Event, LastPacketNat, Network, Route};
+use super::program;
+
+const NICS: usize = 50;
+const NAT: i64 = 255;
+
+// Each NIC runs on its own thread, this only catches one that never stops computing
+const NIC_BUDGET: u64 = 100_000_000;
+
+pub fn boot(nic: &str) -> Result<Network<AddressRouter, LastPacketNat>, Box<dyn Error + 'static>> {
+    Ok(Network::boot(
+        vec![program::load(nic)?; NICS],
+        AddressRouter {
+            nodes: NICS,
+            nat: Some(NAT),
+        },
+        LastPacketNat::default(),
+        Some(NIC_BUDGET),
+    ))
+}
 
-    let mut network: Vec<intcode::Intcode> = vec![];
-    let mut packets: Vec<VecDeque<(i64, i64)>> = vec![];
-    for i in 0..50 {
-        let mut comp = intcode::Intcode::new_with_path(nic.clone());
-        comp = comp.run().add_input(i);
-        packets.push(VecDeque::new());
-        network.push(comp);
+fn stopped(node: usize, status: CompStatus) -> Box<dyn Error + 'static> {
+    match status {
+        CompStatus::Error(error) => Box::new(error),
+        status => format!("NIC {} stopped: {:?}", node, status).into(),
     }
+}
 
-    'main: loop {
-        for (index, comp) in network.iter_mut().enumerate() {
-            let status = comp.next_op();
-
-            match status {
-                intcode::CompStatus::Waiting => {
-                    let packets = packets[index].pop_front().unwrap_or((-1, -1));
-                    comp.add_input_borrowing(packets.0);
-                    if packets.0 != -1 {
-                        comp.add_input_borrowing(packets.1);
-                    }
-                }
-                intcode::CompStatus::Error | intcode::CompStatus::Halted => {
-                    panic!("Should not happen")
-                }
-                _ => {}
-            }
-
-            if comp.peek_outputs().len() == 3 {
-                let output = comp.get_outputs();
-                if output[2] == 255 {
-                    println!("Y value {} sent to packet 255", output[0]);
-                    break 'main;
-                }
-                packets[output[2] as usize].push_back((output[1], output[0]));
+// The y of the first packet sent to the NAT
+pub fn first_nat_packet(nic: &str) -> Result<i64, Box<dyn Error + 'static>> {
+    let network = boot(nic)?;
+
+    for event in network {
+        match event {
+            Event::PacketSent {
+                packet,
+                route: Route::Nat,
+            } => {
+                return Ok(packet.y);
             }
+            Event::NodeStopped { node, status } => return Err(stopped(node, status)),
+            _ => {}
         }
     }
-    Ok(())
-}
 
-#[derive(Debug, PartialEq)]
-enum NodeStatus {
-    Active,
-    Waiting,
-    Idle,
+    Err("The network went idle before anything was sent to 255".into())
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let nic = fs::read_to_string(Path::new("./data/day23.txt"))?;
-
-    let mut nat: Option<(i64, i64)> = None;
+// The first y delivered twice in a row by the NAT to the idle network
+pub fn repeated_nat_delivery(nic: &str) -> Result<i64, Box<dyn Error + 'static>> {
+    let network = boot(nic)?;
     let mut previous_y: Option<i64> = None;
 
-    let mut network: Vec<intcode::Intcode> = vec![];
-    let mut packets: Vec<VecDeque<(i64, i64)>> = vec![];
-
-    let mut idlings: Vec<NodeStatus> = vec![];
-
-    for i in 0..50 {
-        let mut comp = intcode::Intcode::new_with_path(nic.clone());
-        comp = comp.run().add_input(i);
-        packets.push(VecDeque::new());
-        network.push(comp);
-        idlings.push(NodeStatus::Active);
+    for event in network {
+        match event {
+            Event::NatDelivery(packet) => {
+                if previous_y == Some(packet.y) {
+                    return Ok(packet.y);
+                }
+                previous_y = Some(packet.y);
+            }
+            Event::NodeStopped { node, status } => return Err(stopped(node, status)),
+            _ => {}
+        }
     }
 
-    loop {
-        for (index, comp) in network.iter_mut().enumerate() {
-            let status = comp.next_op();
+    Err("The network went idle before the NAT got a packet".into())
+}
 
-            let output_len = comp.peek_outputs().len();
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(first_nat_packet(&read_input("day23")?)?.into())
+}
 
-            if output_len > 0 {
-                idlings[index] = NodeStatus::Active;
-            }
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(repeated_nat_delivery(&read_input("day23")?)?.into())
+}
 
-            if output_len == 3 {
-                let output = comp.get_outputs();
-                if output[2] == 255 {
-                    nat = Some((output[1], output[0]));
-                } else {
-                    packets[output[2] as usize].push_back((output[1], output[0]));
-                    idlings[output[2] as usize] = NodeStatus::Active;
-                }
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days::assembler::assemble;
+
+    // NIC 49 sends (0, 5) to the NAT, every other NIC passes what it gets to the next address
+    // and the last one sends it back to the NAT
+    const RELAY: &str = "
+    in [addr]
+    eq [addr], #49, [last]
+    jz [last], #loop
+    out #255
+    out #0
+    out #5
+loop:
+    in [x]
+    eq [x], #-1, [empty]
+    jnz [empty], #loop
+    in [y]
+    jnz [last], #tonat
+    add [addr], #1, [next]
+    out [next]
+    out [x]
+    out [y]
+    jz #0, #loop
+tonat:
+    out #255
+    out [x]
+    out [y]
+    jz #0, #loop
+addr: db 0
+last: db 0
+x: db 0
+y: db 0
+empty: db 0
+next: db 0
+";
+
+    fn relay() -> String {
+        assemble(RELAY)
+            .unwrap()
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
 
-            match status {
-                intcode::CompStatus::Waiting => {
-                    let mut could_be_idling = output_len == 0;
-
-                    if packets[index].is_empty() {
-                        comp.add_input_borrowing(-1);
-                        could_be_idling &= true;
-                    } else {
-                        let packet = packets[index].pop_front().unwrap();
-                        comp.add_input_borrowing(packet.0);
-                        comp.add_input_borrowing(packet.1);
-
-                        could_be_idling = false;
-                    }
-
-                    idlings[index] = match idlings[index] {
-                        NodeStatus::Active => {
-                            if could_be_idling {
-                                NodeStatus::Waiting
-                            } else {
-                                NodeStatus::Active
-                            }
-                        }
-                        NodeStatus::Waiting => {
-                            if could_be_idling {
-                                NodeStatus::Idle
-                            } else {
-                                NodeStatus::Active
-                            }
-                        }
-                        NodeStatus::Idle => {
-                            if could_be_idling {
-                                NodeStatus::Idle
-                            } else {
-                                NodeStatus::Active
-                            }
-                        }
-                    };
-                }
-                intcode::CompStatus::Error | intcode::CompStatus::Halted => {
-                    panic!("Should not happen")
-                }
-                _ => {}
-            }
-        }
+    #[test]
+    fn first_packet_to_the_nat() {
+        assert_eq!(first_nat_packet(&relay()).unwrap(), 5);
+    }
 
-        let is_truly_idling = idlings.iter().all(|curr| *curr == NodeStatus::Idle);
-
-        if is_truly_idling {
-            if let Some((x, y)) = nat {
-                packets[0].push_back((x, y));
-                idlings[0] = NodeStatus::Active;
-
-                previous_y = if let Some(pre_y) = previous_y {
-                    if pre_y == y {
-                        println!("First repetition: {}", y);
-                        break;
-                    }
-                    Some(y)
-                } else {
-                    Some(y)
-                };
-            }
-        }
+    #[test]
+    fn nat_delivers_the_same_packet_twice() {
+        assert_eq!(repeated_nat_delivery(&relay()).unwrap(), 5);
+    }
+
+    #[test]
+    fn crashing_nics_are_reported() {
+        assert!(first_nat_packet("3,0,99,0").is_err());
     }
-    Ok(())
 }
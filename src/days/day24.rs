@@ -1,10 +1,11 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
 use std::collections::HashMap;
 use std::collections::HashSet;
 
+use super::answer::Answer;
+use super::input::read_input;
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 enum Tile {
     Empty,
@@ -22,7 +23,7 @@ impl GameOfLife for Area {
     fn next_step(&self) -> Self {
         let mut new_map = vec![];
 
-        let offsets = vec![(-1, 0), (1, 0), (0, -1), (0, 1)];
+        let offsets = [(-1, 0), (1, 0), (0, -1), (0, 1)];
 
         for (line_no, line) in self.iter().enumerate() {
             let mut new_line = vec![];
@@ -94,13 +95,13 @@ impl GameOfLife for Area {
 
 type Area = Vec<Vec<Tile>>;
 
-fn prepare_input(input: String) -> Area {
+fn prepare_input(input: &str) -> Area {
     let mut area: Area = vec![];
 
-    for line in input.lines() {
+    for line in input.lines().filter(|line| !line.trim().is_empty()) {
         area.push(
             line.chars()
-                .map(|elem| if elem == '.' { Tile::Empty } else { Tile::Bugs })
+                .map(|elem| if elem == '#' { Tile::Bugs } else { Tile::Empty })
                 .collect::<Vec<Tile>>(),
         );
     }
@@ -108,8 +109,9 @@ fn prepare_input(input: String) -> Area {
     area
 }
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let mut area = prepare_input(fs::read_to_string(Path::new("./data/day24.txt"))?);
+// Biodiversity rating of the first layout seen twice
+pub fn first_repeated_layout(input: &str) -> i64 {
+    let mut area = prepare_input(input);
     let mut layouts: HashSet<i64> = HashSet::new();
 
     let mut layout_code = area.get_score();
@@ -119,9 +121,11 @@ pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
         layout_code = area.get_score();
     }
 
-    println!("First repeating biodiversity: {}", layout_code);
+    layout_code
+}
 
-    Ok(())
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(first_repeated_layout(&read_input("day24")?).into())
 }
 
 #[derive(Debug)]
@@ -170,7 +174,7 @@ impl RecursiveGameOfLife for Area {
     ) -> Self {
         let mut new_map = vec![];
 
-        let offsets = vec![(-1, 0), (1, 0), (0, -1), (0, 1)];
+        let offsets = [(-1, 0), (1, 0), (0, -1), (0, 1)];
 
         for (line_no, line) in self.iter().enumerate() {
             let mut new_line = vec![];
@@ -182,7 +186,7 @@ impl RecursiveGameOfLife for Area {
                         (line_no, tile_no),
                         *offset,
                         recursion_level,
-                        &recursions,
+                        recursions,
                     );
                 }
 
@@ -314,7 +318,7 @@ impl RecursiveGameOfLife for Area {
     }
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
+pub fn recursive_bugs(input: &str, minutes: usize) -> usize {
     let outer_upper_border_score = 31;
     let outer_left_border_score = 1_082_401;
     let outer_right_border_score = 17_318_416;
@@ -325,7 +329,7 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
     let inner_right_border = 8_192;
     let inner_down_border = 131_072;
 
-    let mut area = prepare_input(fs::read_to_string(Path::new("./data/day24.txt"))?);
+    let mut area = prepare_input(input);
     area[2][2] = Tile::Recursion;
 
     let mut outermost_level = 0;
@@ -335,7 +339,7 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
 
     recursive_layouts.insert(0, area);
 
-    for _ in 0..200 {
+    for _ in 0..minutes {
         let mut next_layout: HashMap<isize, Area> = HashMap::new();
 
         let innermost_area_score = recursive_layouts.get(&innermost_level).unwrap().get_score();
@@ -374,15 +378,43 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
         recursive_layouts = next_layout;
     }
 
-    let total_bug_count = recursive_layouts.iter().fold(0, |acc, (_, area)| {
+    recursive_layouts.iter().fold(0, |acc, (_, area)| {
         acc + area.iter().fold(0, |acc, line| {
             acc + line
                 .iter()
                 .fold(0, |acc, tile| acc + if *tile == Tile::Bugs { 1 } else { 0 })
         })
-    });
+    })
+}
+
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(recursive_bugs(&read_input("day24")?, 200).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    println!("Total bug count after 200 minutes: {}", total_bug_count);
+    const ERIS: &str = "....#
+#..#.
+#..##
+..#..
+#....
+";
 
-    Ok(())
+    #[test]
+    fn first_repeated_layout_example() {
+        assert_eq!(first_repeated_layout(ERIS), 2_129_920);
+    }
+
+    #[test]
+    fn one_minute() {
+        let area = GameOfLife::next_step(&prepare_input(ERIS));
+        assert_eq!(area, prepare_input("#..#.\n####.\n###.#\n##.##\n.##.."));
+    }
+
+    #[test]
+    fn recursive_bugs_example() {
+        assert_eq!(recursive_bugs(ERIS, 10), 99);
+    }
 }
@@ -1,63 +1,52 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
 use std::collections::HashMap;
 use std::collections::HashSet;
 
+use super::answer::Answer;
+use super::input::read_input;
+
 type Coordinate = (i32, i32);
 
-fn prepare_input(input: String) -> Vec<Vec<String>> {
-    // We have to use two statement because of the lifetime ellision and "temporary value dropped while borrowed"
-    // The String created won't last long enougth if I use it without storing it
+// Every move of a wire as the unit step it repeats and how many times
+type Movement = (Coordinate, usize);
+
+fn prepare_input(input: &str) -> Result<Vec<Vec<Movement>>, Box<dyn Error + 'static>> {
     input
         .trim()
-        .split('\n')
-        .map(|elem| {
-            elem.split(',')
-                .map(|x| x.to_string())
-                .collect::<Vec<String>>()
+        .lines()
+        .map(|wire| {
+            wire.split(',')
+                .map(|movement| {
+                    let movement = movement.trim();
+                    let step = match movement.chars().next() {
+                        Some('U') => (0, 1),
+                        Some('D') => (0, -1),
+                        Some('L') => (-1, 0),
+                        Some('R') => (1, 0),
+                        _ => return Err(format!("Unknown direction {}", movement).into()),
+                    };
+                    let length = movement[1..]
+                        .parse()
+                        .map_err(|_| format!("Invalid length in {}", movement))?;
+                    Ok((step, length))
+                })
+                .collect()
         })
-        .collect::<Vec<_>>()
+        .collect()
 }
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let wires_path = prepare_input(fs::read_to_string(Path::new("./data/day3.txt"))?);
+pub fn closest_crossing(input: &str) -> Result<Option<i32>, Box<dyn Error + 'static>> {
+    let wires_path = prepare_input(input)?;
     let mut wiring: HashSet<Coordinate> = HashSet::new();
     let mut smallest_dist: Option<i32> = None;
 
     for (index, path) in wires_path.iter().enumerate() {
         let mut coord = (0, 0);
-        for movement in path {
-            let length = movement[1..].parse::<i32>().unwrap_or(0);
-            let mut lateral = true;
-            let mut positive = true;
-
-            match movement.chars().next().unwrap() {
-                'U' => {
-                    lateral = false;
-                }
-                'D' => {
-                    lateral = false;
-                    positive = false;
-                }
-                'L' => positive = false,
-                'R' => {}
-                a => {
-                    println!("{}", a);
-                    panic!("Should NOT happen")
-                }
-            }
-
-            for _ in 0..length {
-                let offset = if positive { 1 } else { -1 };
-                let temp_coord = if lateral {
-                    (coord.0 + offset, coord.1)
-                } else {
-                    (coord.0, coord.1 + offset)
-                };
-                coord = temp_coord;
-                if !wiring.insert(temp_coord) && index == 1 {
+        for (step, length) in path {
+            for _ in 0..*length {
+                coord = (coord.0 + step.0, coord.1 + step.1);
+                if !wiring.insert(coord) && index == 1 {
                     let curr_dist = coord.0.abs() + coord.1.abs();
                     // get man distance
                     smallest_dist = if let Some(dist) = smallest_dist {
@@ -74,13 +63,11 @@ pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
         }
     }
 
-    println!("Smallest dist is: {}", smallest_dist.unwrap_or(0));
-
-    Ok(())
+    Ok(smallest_dist)
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let wires_path = prepare_input(fs::read_to_string(Path::new("./data/day3.txt"))?);
+pub fn fastest_crossing(input: &str) -> Result<Option<usize>, Box<dyn Error + 'static>> {
+    let wires_path = prepare_input(input)?;
     let mut wiring: HashMap<Coordinate, (usize, usize)> = HashMap::new();
     let mut smallest_timing: Option<usize> = None;
 
@@ -88,35 +75,10 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
         let flag = index + 1;
         let mut coord = (0, 0);
         let mut steps = 0;
-        for movement in path {
-            let length = movement[1..].parse::<i32>().unwrap_or(0);
-            let mut lateral = true;
-            let mut positive = true;
-
-            match movement.chars().next().unwrap() {
-                'U' => {
-                    lateral = false;
-                }
-                'D' => {
-                    lateral = false;
-                    positive = false;
-                }
-                'L' => positive = false,
-                'R' => {}
-                a => {
-                    println!("{}", a);
-                    panic!("Should NOT happen")
-                }
-            }
-
-            for _ in 0..length {
+        for (step, length) in path {
+            for _ in 0..*length {
                 steps += 1;
-                let offset = if positive { 1 } else { -1 };
-                coord = if lateral {
-                    (coord.0 + offset, coord.1)
-                } else {
-                    (coord.0, coord.1 + offset)
-                };
+                coord = (coord.0 + step.0, coord.1 + step.1);
                 if let Some(crossroad) = wiring.get(&coord) {
                     if flag & crossroad.1 != flag {
                         let total = crossroad.0 + steps;
@@ -133,9 +95,70 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
         }
     }
 
-    println!("Smallest timing: {}", smallest_timing.unwrap_or(0));
+    Ok(smallest_timing)
+}
+
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(closest_crossing(&read_input("day3")?)?
+        .ok_or("The wires never cross")?
+        .into())
+}
 
-    Ok(())
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(fastest_crossing(&read_input("day3")?)?
+        .ok_or("The wires never cross")?
+        .into())
 }
 
 //2714 too low
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLES: [(&str, i32, usize); 3] = [
+        ("R8,U5,L5,D3\nU7,R6,D4,L4", 6, 30),
+        (
+            "R75,D30,R83,U83,L12,D49,R71,U7,L72\nU62,R66,U55,R34,D71,R55,D58,R83",
+            159,
+            610,
+        ),
+        (
+            "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51\nU98,R91,D20,R16,D67,R40,U7,R15,U6,R7",
+            135,
+            410,
+        ),
+    ];
+
+    #[test]
+    fn closest_crossing_examples() {
+        for (wires, distance, _) in EXAMPLES.iter() {
+            assert_eq!(closest_crossing(wires).unwrap(), Some(*distance));
+        }
+    }
+
+    #[test]
+    fn fastest_crossing_examples() {
+        for (wires, _, steps) in EXAMPLES.iter() {
+            assert_eq!(fastest_crossing(wires).unwrap(), Some(*steps));
+        }
+    }
+
+    #[test]
+    fn parallel_wires_never_cross() {
+        assert_eq!(closest_crossing("U5\nR1,U5").unwrap(), None);
+        assert_eq!(fastest_crossing("U5\nR1,U5").unwrap(), None);
+    }
+
+    #[test]
+    fn unknown_directions_are_errors() {
+        assert_eq!(
+            closest_crossing("R8,X5\nU7").unwrap_err().to_string(),
+            "Unknown direction X5"
+        );
+        assert_eq!(
+            fastest_crossing("R8\nU7,Lx").unwrap_err().to_string(),
+            "Invalid length in Lx"
+        );
+    }
+}
@@ -2,49 +2,62 @@ use std::error::Error;
 
 use regex::Regex;
 
+use super::answer::Answer;
+
 const START: u32 = 134_792;
 const END: u32 = 675_810;
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let following_number = Regex::new(r"11|22|33|44|55|66|77|88|99").unwrap(); //Crate can't handle backreferences...
-    let mut counter = 0;
-
-    for i in START..END {
-        let num_in_string = i.to_string();
-        if following_number.is_match(&num_in_string) {
-            let mut reordered: Vec<char> = num_in_string.clone().chars().collect();
-            reordered.sort_by(|a, b| a.cmp(b));
-            let reordered = reordered.iter().collect::<String>();
-
-            if reordered == num_in_string {
-                counter += 1;
-            }
-        }
+pub fn any_pair() -> Regex {
+    Regex::new(r"11|22|33|44|55|66|77|88|99").unwrap() //Crate can't handle backreferences...
+}
+
+// A pair that is not part of a larger group
+pub fn exact_pair() -> Regex {
+    Regex::new(r"([^1]|^)11([^1]|$)|([^2]|^)22([^2]|$)|([^3]|^)33([^3]|$)|([^4]|^)44([^4]|$)|([^5]|^)55([^5]|$)|([^6]|^)66([^6]|$)|([^7]|^)77([^7]|$)|([^8]|^)88([^8]|$)|([^9]|^)99([^9]|$)").unwrap()
+    //Crate can't handle backreferences...
+}
+
+pub fn is_password(num_in_string: &str, following_number: &Regex) -> bool {
+    if following_number.is_match(num_in_string) {
+        let mut reordered: Vec<char> = num_in_string.chars().collect();
+        reordered.sort();
+        let reordered = reordered.iter().collect::<String>();
+
+        reordered == num_in_string
+    } else {
+        false
     }
+}
 
-    println!("Number of passwords: {}", counter);
+pub fn count_passwords(following_number: &Regex) -> usize {
+    (START..END)
+        .filter(|i| is_password(&i.to_string(), following_number))
+        .count()
+}
 
-    Ok(())
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(count_passwords(&any_pair()).into())
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let following_number = Regex::new(r"([^1]|^)11([^1]|$)|([^2]|^)22([^2]|$)|([^3]|^)33([^3]|$)|([^4]|^)44([^4]|$)|([^5]|^)55([^5]|$)|([^6]|^)66([^6]|$)|([^7]|^)77([^7]|$)|([^8]|^)88([^8]|$)|([^9]|^)99([^9]|$)").unwrap(); //Crate can't handle backreferences...
-    let mut counter = 0;
-
-    for i in START..END {
-        let num_in_string = i.to_string();
-        if following_number.is_match(&num_in_string) {
-            let mut reordered: Vec<char> = num_in_string.clone().chars().collect();
-            reordered.sort_by(|a, b| a.cmp(b));
-            let reordered = reordered.iter().collect::<String>();
-
-            if reordered == num_in_string {
-                counter += 1;
-            }
-        }
-    }
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(count_passwords(&exact_pair()).into())
+}
 
-    println!("Number of passwords: {}", counter);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(())
+    #[test]
+    fn first_star_examples() {
+        assert!(is_password("111111", &any_pair()));
+        assert!(!is_password("223450", &any_pair()));
+        assert!(!is_password("123789", &any_pair()));
+    }
+
+    #[test]
+    fn second_star_examples() {
+        assert!(is_password("112233", &exact_pair()));
+        assert!(!is_password("123444", &exact_pair()));
+        assert!(is_password("111122", &exact_pair()));
+    }
 }
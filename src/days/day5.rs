@@ -1,29 +1,56 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
-use super::intcode::Intcode;
+use super::answer::Answer;
+use super::input::read_input;
+use super::intcode::{CompStatus, Intcode};
+use super::program;
 
-fn prepare_file(input: String) -> Vec<i64> {
-    input
-        .trim()
-        .split(',')
-        .map(|x| (x.trim().parse::<i64>().unwrap_or(0)))
-        .collect::<Vec<_>>()
+// The diagnostic code is the last output of the program
+pub fn diagnostic(program: &str, system_id: i64) -> Result<Answer, Box<dyn Error + 'static>> {
+    let mem = program::load(program)?;
+    let int_machine = Intcode::new(mem).add_input(system_id).run();
+
+    match int_machine.status {
+        CompStatus::Halted => Ok(int_machine.output.into()),
+        status => Err(format!("The diagnostic program stopped: {:?}", status).into()),
+    }
+}
+
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    diagnostic(&read_input("day5")?, 1)
 }
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let mem = prepare_file(fs::read_to_string(Path::new("./data/day5.txt"))?);
-    let int_machine = Intcode::new(mem).add_input(1).run();
-    println!("The final output is: {}", int_machine.output);
-    Ok(())
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    diagnostic(&read_input("day5")?, 5)
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let mem = prepare_file(fs::read_to_string(Path::new("./data/day5.txt"))?);
-    let int_machine = Intcode::new(mem).add_input(5).run();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AROUND_EIGHT: &str = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+
+    #[test]
+    fn comparisons() {
+        for program in &["3,9,8,9,10,9,4,9,99,-1,8", "3,3,1108,-1,8,3,4,3,99"] {
+            assert_eq!(diagnostic(program, 8).unwrap(), Answer::Number(1));
+            assert_eq!(diagnostic(program, 7).unwrap(), Answer::Number(0));
+        }
+        for program in &["3,9,7,9,10,9,4,9,99,-1,8", "3,3,1107,-1,8,3,4,3,99"] {
+            assert_eq!(diagnostic(program, 7).unwrap(), Answer::Number(1));
+            assert_eq!(diagnostic(program, 8).unwrap(), Answer::Number(0));
+        }
+    }
 
-    println!("The final output is: {}", int_machine.output);
+    #[test]
+    fn jumps() {
+        assert_eq!(diagnostic(AROUND_EIGHT, 7).unwrap(), Answer::Number(999));
+        assert_eq!(diagnostic(AROUND_EIGHT, 8).unwrap(), Answer::Number(1000));
+        assert_eq!(diagnostic(AROUND_EIGHT, 9).unwrap(), Answer::Number(1001));
+    }
 
-    Ok(())
+    #[test]
+    fn waiting_for_input_is_an_error() {
+        assert!(diagnostic("3,0,3,0,99", 1).is_err());
+    }
 }
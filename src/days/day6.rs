@@ -1,10 +1,11 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
 use std::collections::HashMap;
 
-pub fn prepare_input(input: String) -> HashMap<String, Vec<String>> {
+use super::answer::Answer;
+use super::input::read_input;
+
+pub fn prepare_input(input: &str) -> HashMap<String, Vec<String>> {
     let nodes = input
         .trim()
         .split('\n')
@@ -19,22 +20,23 @@ pub fn prepare_input(input: String) -> HashMap<String, Vec<String>> {
     let mut tree: HashMap<String, Vec<String>> = HashMap::new();
 
     for node in nodes {
-        let childs = tree.entry(node[0].clone()).or_insert_with(|| vec![]);
+        let childs = tree.entry(node[0].clone()).or_default();
         childs.push(node[1].clone());
     }
 
     tree
 }
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let tree = prepare_input(fs::read_to_string(Path::new("./data/day6.txt"))?);
+pub fn orbit_count(tree: &HashMap<String, Vec<String>>) -> usize {
     let mut count = 0;
 
-    bfs_first_star(&tree, vec!["COM".to_string()], 0, &mut count);
+    bfs_first_star(tree, vec!["COM".to_string()], 0, &mut count);
 
-    println!("Number of orbits: {}", count);
+    count
+}
 
-    Ok(())
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(orbit_count(&prepare_input(&read_input("day6")?)).into())
 }
 
 fn bfs_first_star(
@@ -59,12 +61,15 @@ fn bfs_first_star(
     }
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let tree = prepare_input(fs::read_to_string(Path::new("./data/day6.txt"))?);
-
+pub fn orbital_transfers(
+    tree: &HashMap<String, Vec<String>>,
+) -> Result<usize, Box<dyn Error + 'static>> {
     // I could try and implement a tree with backreference and use an hashset but it's kind of a pain soo...
-    let (mut com_to_san, _) = route_to(&tree, &"SAN".to_string(), vec!["COM".to_string()]);
-    let (mut com_to_me, _) = route_to(&tree, &"YOU".to_string(), vec!["COM".to_string()]);
+    let (mut com_to_san, found_san) = route_to(tree, "SAN", vec!["COM".to_string()]);
+    let (mut com_to_me, found_me) = route_to(tree, "YOU", vec!["COM".to_string()]);
+    if !found_san || !found_me {
+        return Err("YOU and SAN must both orbit around COM".into());
+    }
 
     let mut san_to_me: Vec<String> = vec![];
 
@@ -85,9 +90,11 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
         }
     }
 
-    println!("I need {} jumps to join Santa", san_to_me.len() - 2);
+    Ok(san_to_me.len() - 2)
+}
 
-    Ok(())
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(orbital_transfers(&prepare_input(&read_input("day6")?))?.into())
 }
 
 fn route_to(
@@ -115,3 +122,26 @@ fn route_to(
 
     (route, false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORBITS: &str = "COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L";
+
+    #[test]
+    fn orbit_count_example() {
+        assert_eq!(orbit_count(&prepare_input(ORBITS)), 42);
+    }
+
+    #[test]
+    fn orbital_transfers_example() {
+        let tree = prepare_input(&format!("{}\nK)YOU\nI)SAN\n", ORBITS));
+        assert_eq!(orbital_transfers(&tree).unwrap(), 4);
+    }
+
+    #[test]
+    fn santa_must_be_in_the_map() {
+        assert!(orbital_transfers(&prepare_input(ORBITS)).is_err());
+    }
+}
@@ -1,129 +1,71 @@
-use itertools::Itertools;
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
-use super::intcode::{CompStatus, Intcode};
+use super::answer::Answer;
+use super::input::read_input;
+use super::pipeline::{self, Topology};
+use super::program;
 
-fn prepare_file(input: String) -> Vec<i64> {
-    input
-        .trim()
-        .split(',')
-        .map(|x| (x.trim().parse::<i64>().unwrap_or(0)))
-        .collect::<Vec<_>>()
-}
-
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let memory = prepare_file(fs::read_to_string(Path::new("./data/day7.txt"))?);
-
-    let permutations_it = (0..5).permutations(5);
-
-    let mut max_thruster: Option<i64> = None;
-    let mut permutation_str: String = String::from("None");
-
-    for permutation in permutations_it {
-        let current_permutation_str = permutation.iter().map(|elem| elem.to_string()).collect();
-
-        let first_amp = Intcode::new(memory.clone())
-            .add_input(permutation[0])
-            .run()
-            .add_input(0)
-            .run();
-        let second_amp = Intcode::new(memory.clone())
-            .add_input(permutation[1])
-            .run()
-            .add_input(first_amp.output)
-            .run();
-        let third_amp = Intcode::new(memory.clone())
-            .add_input(permutation[2])
-            .run()
-            .add_input(second_amp.output)
-            .run();
-        let fourth_amp = Intcode::new(memory.clone())
-            .add_input(permutation[3])
-            .run()
-            .add_input(third_amp.output)
-            .run();
-        let fifth_amp = Intcode::new(memory.clone())
-            .add_input(permutation[4])
-            .run()
-            .add_input(fourth_amp.output)
-            .run();
+pub fn solve(
+    program: &str,
+    phases: &[i64],
+    topology: Topology,
+) -> Result<Answer, Box<dyn Error + 'static>> {
+    let memory = program::load(program)?;
+    let (_, thruster) = pipeline::best_phases(&memory, phases, topology)?;
 
-        let thruster = fifth_amp.output;
-
-        max_thruster = if let Some(current_max) = max_thruster {
-            if current_max < thruster {
-                permutation_str = current_permutation_str;
-                Some(thruster)
-            } else {
-                Some(current_max)
-            }
-        } else {
-            permutation_str = current_permutation_str;
-            Some(thruster)
-        }
-    }
-
-    println!(
-        "Max thruster: {} for permutation: {}",
-        max_thruster.unwrap_or(-1),
-        permutation_str
-    );
-
-    Ok(())
+    Ok(thruster.into())
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let memory = prepare_file(fs::read_to_string(Path::new("./data/day7.txt"))?);
-
-    let permutations_it = (5..10).permutations(5);
-
-    let mut max_thruster: Option<i64> = None;
-    let mut permutation_str: String = String::from("None");
-
-    for permutation in permutations_it {
-        let current_permutation_str = permutation.iter().map(|elem| elem.to_string()).collect();
-
-        let mut first_amp = Intcode::new(memory.clone()).add_input(permutation[0]).run();
-
-        let mut second_amp = Intcode::new(memory.clone()).add_input(permutation[1]).run();
-        let mut third_amp = Intcode::new(memory.clone()).add_input(permutation[2]).run();
-
-        let mut fourth_amp = Intcode::new(memory.clone()).add_input(permutation[3]).run();
-
-        let mut fifth_amp = Intcode::new(memory.clone()).add_input(permutation[4]).run();
-
-        let mut input = 0;
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    solve(&read_input("day7")?, &[0, 1, 2, 3, 4], Topology::Chain)
+}
 
-        while fifth_amp.status != CompStatus::Halted {
-            first_amp = first_amp.add_input(input).run();
-            second_amp = second_amp.add_input(first_amp.output).run();
-            third_amp = third_amp.add_input(second_amp.output).run();
-            fourth_amp = fourth_amp.add_input(third_amp.output).run();
-            fifth_amp = fifth_amp.add_input(fourth_amp.output).run();
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    solve(&read_input("day7")?, &[5, 6, 7, 8, 9], Topology::Ring)
+}
 
-            input = fifth_amp.output;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chain_examples() {
+        let examples = [
+            ("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0", 43210),
+            (
+                "3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0",
+                54321,
+            ),
+            (
+                "3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0",
+                65210,
+            ),
+        ];
+        for (program, thruster) in examples.iter() {
+            assert_eq!(
+                solve(program, &[0, 1, 2, 3, 4], Topology::Chain).unwrap(),
+                Answer::Number(*thruster)
+            );
         }
+    }
 
-        max_thruster = if let Some(current_max) = max_thruster {
-            if current_max < input {
-                permutation_str = current_permutation_str;
-                Some(input)
-            } else {
-                Some(current_max)
-            }
-        } else {
-            permutation_str = current_permutation_str;
-            Some(input)
+    #[test]
+    fn ring_examples() {
+        let examples = [
+            (
+                "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
+                139_629_729,
+            ),
+            (
+                "3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,-5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10",
+                18216,
+            ),
+        ];
+        for (program, thruster) in examples.iter() {
+            assert_eq!(
+                solve(program, &[5, 6, 7, 8, 9], Topology::Ring).unwrap(),
+                Answer::Number(*thruster)
+            );
         }
     }
-
-    println!(
-        "Max thruster: {} for permutation: {}",
-        max_thruster.unwrap_or(-1),
-        permutation_str
-    );
-
-    Ok(())
 }
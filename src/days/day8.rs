@@ -1,25 +1,24 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
+
+use super::answer::Answer;
+use super::input::read_input;
 
 const WIDTH: usize = 25;
-const PAGE_SIZE: usize = 25 * 6;
+pub const HEIGHT: usize = 6;
 
-fn prepare_file(input: String) -> Vec<u32> {
+pub fn prepare_file(input: &str) -> Vec<u32> {
     input
         .trim()
         .chars()
-        .map(|c| (c.to_digit(10).unwrap_or(0)))
+        .map(|c| c.to_digit(10).unwrap_or(0))
         .collect::<Vec<_>>()
 }
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let img = prepare_file(fs::read_to_string(Path::new("./data/day8.txt"))?);
-
+pub fn checksum(img: &[u32], page_size: usize) -> usize {
     let mut min_num_of_zero: Option<usize> = None;
     let mut checksum = 0;
 
-    for page in img.chunks(PAGE_SIZE) {
+    for page in img.chunks(page_size) {
         let (zero_count, one_count, two_count) = page.iter().fold((0, 0, 0), |acc, elem| {
             if *elem == 0 {
                 (acc.0 + 1, acc.1, acc.2)
@@ -44,16 +43,14 @@ pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
         }
     }
 
-    println!("Checksum: {}", checksum);
-
-    Ok(())
+    checksum
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let img = prepare_file(fs::read_to_string(Path::new("./data/day8.txt"))?);
-    let mut result: Vec<u32> = vec![2; PAGE_SIZE];
+// Transparent pixels (2) show the layers below them
+pub fn decode(img: &[u32], page_size: usize) -> Vec<u32> {
+    let mut result: Vec<u32> = vec![2; page_size];
 
-    for page in img.chunks(PAGE_SIZE) {
+    for page in img.chunks(page_size) {
         for (index, elem) in page.iter().enumerate() {
             result[index] = if result[index] == 2 {
                 *elem
@@ -63,16 +60,44 @@ pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
         }
     }
 
-    println!("Printing result: ");
+    result
+}
 
-    for (index, value) in result.iter().enumerate() {
-        let character = if *value == 0 { "\u{25A1}" } else { "■" };
-        if index % WIDTH == 0 {
-            println!();
-        }
-        print!("{}", character);
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let img = prepare_file(&read_input("day8")?);
+    Ok(checksum(&img, WIDTH * HEIGHT).into())
+}
+
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    let img = prepare_file(&read_input("day8")?);
+    let image = decode(&img, WIDTH * HEIGHT)
+        .chunks(WIDTH)
+        .map(|row| {
+            row.iter()
+                .map(|value| if *value == 0 { '\u{25A1}' } else { '■' })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(Answer::Image(image))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksum_example() {
+        // Both 3x2 layers have no 0, the first one is kept
+        assert_eq!(checksum(&prepare_file("123456789012"), 3 * 2), 1);
     }
-    println!();
 
-    Ok(())
+    #[test]
+    fn decode_example() {
+        assert_eq!(
+            decode(&prepare_file("0222112222120000"), 2 * 2),
+            vec![0, 1, 1, 0]
+        );
+    }
 }
@@ -1,27 +1,48 @@
 use std::error::Error;
-use std::fs;
-use std::path::Path;
 
-use super::intcode::Intcode;
+use super::answer::Answer;
+use super::input::read_input;
+use super::intcode::{CompStatus, Intcode};
+use super::program;
 
-fn prepare_file(input: String) -> Vec<i64> {
-    input
-        .trim()
-        .split(',')
-        .map(|x| (x.trim().parse::<i64>().unwrap_or(0)))
-        .collect::<Vec<_>>()
+pub fn boost(program: &str, mode: i64) -> Result<Answer, Box<dyn Error + 'static>> {
+    let memory = program::load(program)?;
+    let intmachine = Intcode::new(memory).add_input(mode).run();
+
+    match intmachine.status {
+        CompStatus::Halted => Ok(intmachine.output.into()),
+        status => Err(format!("BOOST stopped: {:?}", status).into()),
+    }
+}
+
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    boost(&read_input("day9")?, 1)
 }
 
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let memory = prepare_file(fs::read_to_string(Path::new("./data/day9.txt"))?);
-    let intmachine = Intcode::new(memory).add_input(1).run();
-    println!("{}, {:?}", intmachine.output, intmachine.status);
-    Ok(())
+pub fn second_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    boost(&read_input("day9")?, 2)
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    let memory = prepare_file(fs::read_to_string(Path::new("./data/day9.txt"))?);
-    let intmachine = Intcode::new(memory).add_input(2).run();
-    println!("{}, {:?}", intmachine.output, intmachine.status);
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_base_and_large_memory() {
+        // Outputs a copy of itself, the last value being its final 99
+        let quine = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+        assert_eq!(boost(quine, 1).unwrap(), Answer::Number(99));
+    }
+
+    #[test]
+    fn large_numbers() {
+        assert_eq!(
+            boost("1102,34915192,34915192,7,4,7,99,0", 1).unwrap(),
+            Answer::Number(1_219_070_632_396_864)
+        );
+        assert_eq!(
+            boost("104,1125899906842624,99", 1).unwrap(),
+            Answer::Number(1_125_899_906_842_624)
+        );
+    }
 }
@@ -0,0 +1,542 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, BufRead, Write};
+
+use super::disassembler;
+use super::intcode::{CompStatus, Intcode, IntcodeError, Opcode};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum IoBreak {
+    Off,
+    Any,
+    Value(i64),
+}
+
+impl IoBreak {
+    fn matches(self, value: Option<i64>) -> bool {
+        match self {
+            IoBreak::Off => false,
+            IoBreak::Any => true,
+            IoBreak::Value(expected) => value == Some(expected),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StopReason {
+    Breakpoint(usize),
+    Watchpoint { address: usize, old: i64, new: i64 },
+    Input(Option<i64>),
+    Output(i64),
+    StepsDone,
+    Waiting,
+    Halted,
+    OutOfBudget,
+    Error(IntcodeError),
+}
+
+#[derive(Debug)]
+pub struct Debugger {
+    pub machine: Intcode,
+    breakpoints: BTreeSet<usize>,
+    // Last value seen at each watched address
+    watchpoints: BTreeMap<usize, i64>,
+    pub input_break: IoBreak,
+    pub output_break: IoBreak,
+}
+
+impl Debugger {
+    pub fn new(machine: Intcode) -> Self {
+        Debugger {
+            machine,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeMap::new(),
+            input_break: IoBreak::Off,
+            output_break: IoBreak::Off,
+        }
+    }
+
+    fn peek(&self, address: usize) -> i64 {
+        // Memory past the end reads as 0 for the machine, keep the same view here
+        self.machine.memory().get(address).cloned().unwrap_or(0)
+    }
+
+    pub fn add_breakpoint(&mut self, address: usize) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn add_watchpoint(&mut self, address: usize) {
+        let value = self.peek(address);
+        self.watchpoints.insert(address, value);
+    }
+
+    pub fn remove(&mut self, address: usize) -> bool {
+        let breakpoint = self.breakpoints.remove(&address);
+        let watchpoint = self.watchpoints.remove(&address).is_some();
+        breakpoint || watchpoint
+    }
+
+    fn status_stop(&self) -> Option<StopReason> {
+        match self.machine.status {
+            CompStatus::Running => None,
+            CompStatus::Waiting => Some(StopReason::Waiting),
+            CompStatus::Halted => Some(StopReason::Halted),
+            CompStatus::OutOfBudget => Some(StopReason::OutOfBudget),
+            CompStatus::Error(error) => Some(StopReason::Error(error)),
+        }
+    }
+
+    // Executes a single instruction, returns why the machine should stop afterwards if it should
+    pub fn step(&mut self) -> Option<StopReason> {
+        if self.machine.status == CompStatus::Waiting && !self.machine.inputs().is_empty() {
+            self.machine.status = CompStatus::Running;
+        }
+        if let Some(reason) = self.status_stop() {
+            return Some(reason);
+        }
+
+        let outputs_before = self.machine.peek_outputs().len();
+        self.machine.status = self.machine.next_op();
+
+        if self.machine.peek_outputs().len() > outputs_before
+            && self.output_break.matches(Some(self.machine.output))
+        {
+            return Some(StopReason::Output(self.machine.output));
+        }
+
+        for (address, old) in self.watchpoints.iter_mut() {
+            let new = self.machine.memory().get(*address).cloned().unwrap_or(0);
+            if new != *old {
+                let reason = StopReason::Watchpoint {
+                    address: *address,
+                    old: *old,
+                    new,
+                };
+                *old = new;
+                return Some(reason);
+            }
+        }
+
+        self.status_stop()
+    }
+
+    // Breakpoints and input breaks are checked before the instruction at the current index runs
+    fn break_before(&self) -> Option<StopReason> {
+        let index = self.machine.index();
+        if self.breakpoints.contains(&index) {
+            return Some(StopReason::Breakpoint(index));
+        }
+
+        let is_input = self.peek(index) % 100 == Opcode::Input.code();
+        let next_input = self.machine.inputs().front().cloned();
+        if is_input && self.input_break.matches(next_input) {
+            return Some(StopReason::Input(next_input));
+        }
+
+        None
+    }
+
+    pub fn step_n(&mut self, steps: usize) -> StopReason {
+        for step in 0..steps {
+            if step > 0 {
+                if let Some(reason) = self.break_before() {
+                    return reason;
+                }
+            }
+            if let Some(reason) = self.step() {
+                return reason;
+            }
+        }
+        StopReason::StepsDone
+    }
+
+    // Runs until something stops the machine, the instruction at the current index always executes
+    // so resuming from a breakpoint doesn't hit it again straight away
+    pub fn resume(&mut self) -> StopReason {
+        if let Some(reason) = self.step() {
+            return reason;
+        }
+        loop {
+            if let Some(reason) = self.break_before() {
+                return reason;
+            }
+            if let Some(reason) = self.step() {
+                return reason;
+            }
+        }
+    }
+
+    fn current_instruction(&self) -> String {
+        let index = self.machine.index();
+        match disassembler::decode_at(self.machine.memory(), index) {
+            Some(instruction) => format!("{:04}: {}", index, instruction),
+            None => format!("{:04}: db {}", index, self.peek(index)),
+        }
+    }
+
+    fn describe(&self, reason: StopReason) -> String {
+        let reason = match reason {
+            StopReason::Breakpoint(address) => format!("Breakpoint at {}", address),
+            StopReason::Watchpoint { address, old, new } => {
+                format!("Watchpoint: [{}] changed from {} to {}", address, old, new)
+            }
+            StopReason::Input(Some(value)) => format!("About to read input {}", value),
+            StopReason::Input(None) => "About to read input, none pending".to_string(),
+            StopReason::Output(value) => format!("Output {}", value),
+            StopReason::StepsDone => "Stepped".to_string(),
+            StopReason::Waiting => "Waiting for input".to_string(),
+            StopReason::Halted => "Halted".to_string(),
+            StopReason::OutOfBudget => format!(
+                "Step budget of {} spent",
+                self.machine.budget().unwrap_or(0)
+            ),
+            StopReason::Error(error) => format!("Error: {}", error),
+        };
+        format!("{}\n{}", reason, self.current_instruction())
+    }
+
+    fn registers(&self) -> String {
+        format!(
+            "index: {}, relative_offset: {}, status: {:?}, last output: {}, steps: {}",
+            self.machine.index(),
+            self.machine.relative_offset(),
+            self.machine.status,
+            self.machine.output,
+            self.machine.steps()
+        )
+    }
+
+    // Only the cells the program has, a count past the end of memory is cut short
+    fn memory_dump(&self, start: usize, count: usize) -> String {
+        let size = self.machine.memory().len();
+        if start >= size {
+            return format!(
+                "Address {} is past the end of memory ({} cells)",
+                start, size
+            );
+        }
+        let end = start.checked_add(count).map_or(size, |end| end.min(size));
+
+        (start..end)
+            .collect::<Vec<_>>()
+            .chunks(8)
+            .map(|chunk| {
+                let values = chunk
+                    .iter()
+                    .map(|address| format!("{:>6}", self.peek(*address)))
+                    .collect::<String>();
+                format!("{:04}:{}", chunk[0], values)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn listing(&self, start: usize, count: usize) -> String {
+        let size = self.machine.memory().len();
+        if start >= size {
+            return format!(
+                "Address {} is past the end of memory ({} cells)",
+                start, size
+            );
+        }
+
+        let mut address = start;
+        let mut lines = vec![];
+        for _ in 0..count {
+            if address >= size {
+                break;
+            }
+            match disassembler::decode_at(self.machine.memory(), address) {
+                Some(instruction) => {
+                    lines.push(format!("{:04}: {}", address, instruction));
+                    address = instruction.next_address();
+                }
+                None => {
+                    lines.push(format!("{:04}: db {}", address, self.peek(address)));
+                    address += 1;
+                }
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn pending_io(&mut self) -> String {
+        let inputs = self.machine.inputs().iter().cloned().collect::<Vec<_>>();
+        let outputs = self
+            .machine
+            .peek_outputs()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        format!("inputs: {:?}\noutputs: {:?}", inputs, outputs)
+    }
+
+    fn points(&self) -> String {
+        format!(
+            "breakpoints: {:?}\nwatchpoints: {:?}\ninput break: {:?}, output break: {:?}",
+            self.breakpoints.iter().collect::<Vec<_>>(),
+            self.watchpoints.keys().collect::<Vec<_>>(),
+            self.input_break,
+            self.output_break
+        )
+    }
+
+    // Returns the text to print, None when the session should end
+    pub fn execute(&mut self, command: &str) -> Option<String> {
+        let mut words = command.split_whitespace();
+        let name = match words.next() {
+            Some(name) => name,
+            None => return Some(String::new()),
+        };
+        let arguments = words.collect::<Vec<_>>();
+        let number = |index: usize, default: usize| {
+            arguments
+                .get(index)
+                .map(|argument| argument.parse::<usize>().ok())
+                .unwrap_or(Some(default))
+        };
+
+        let text = match name {
+            "q" | "quit" => return None,
+            "h" | "help" => HELP.to_string(),
+            "s" | "step" => match number(0, 1) {
+                Some(steps) => {
+                    let reason = self.step_n(steps);
+                    self.describe(reason)
+                }
+                None => "Usage: step [count]".to_string(),
+            },
+            "c" | "continue" => {
+                let reason = self.resume();
+                self.describe(reason)
+            }
+            "b" | "break" => match number(0, self.machine.index()) {
+                Some(address) => {
+                    self.add_breakpoint(address);
+                    format!("Breakpoint set at {}", address)
+                }
+                None => "Usage: break <address>".to_string(),
+            },
+            "w" | "watch" => match arguments.first().and_then(|a| a.parse::<usize>().ok()) {
+                Some(address) => {
+                    self.add_watchpoint(address);
+                    format!("Watching [{}] = {}", address, self.peek(address))
+                }
+                None => "Usage: watch <address>".to_string(),
+            },
+            "delete" => match arguments.first().and_then(|a| a.parse::<usize>().ok()) {
+                Some(address) if self.remove(address) => format!("Removed {}", address),
+                Some(address) => format!("Nothing set at {}", address),
+                None => "Usage: delete <address>".to_string(),
+            },
+            "bi" | "break-input" | "bo" | "break-output" => {
+                let condition = match arguments.first() {
+                    None | Some(&"any") => Some(IoBreak::Any),
+                    Some(&"off") => Some(IoBreak::Off),
+                    Some(value) => value.parse::<i64>().ok().map(IoBreak::Value),
+                };
+                match condition {
+                    Some(condition) if name == "bi" || name == "break-input" => {
+                        self.input_break = condition;
+                        format!("Input break: {:?}", condition)
+                    }
+                    Some(condition) => {
+                        self.output_break = condition;
+                        format!("Output break: {:?}", condition)
+                    }
+                    None => format!("Usage: {} [off|any|<value>]", name),
+                }
+            }
+            "l" | "list" => self.points(),
+            "r" | "regs" => self.registers(),
+            "m" | "mem" => match (number(0, self.machine.index()), number(1, 16)) {
+                (Some(start), Some(count)) => self.memory_dump(start, count),
+                _ => "Usage: mem [start] [count]".to_string(),
+            },
+            "d" | "disasm" => match (number(0, self.machine.index()), number(1, 10)) {
+                (Some(start), Some(count)) => self.listing(start, count),
+                _ => "Usage: disasm [start] [count]".to_string(),
+            },
+            "budget" => {
+                let budget = match arguments.first() {
+                    Some(&"off") => Some(None),
+                    Some(value) => value.parse::<u64>().ok().map(Some),
+                    None => Some(self.machine.budget()),
+                };
+                match budget {
+                    Some(budget) => {
+                        self.machine.set_budget(budget);
+                        match budget {
+                            Some(budget) => {
+                                format!("Budget: {} steps, {} used", budget, self.machine.steps())
+                            }
+                            None => format!("No budget, {} steps used", self.machine.steps()),
+                        }
+                    }
+                    None => "Usage: budget [off|<steps>]".to_string(),
+                }
+            }
+            "io" => self.pending_io(),
+            "i" | "input" => {
+                let values = arguments
+                    .iter()
+                    .map(|value| value.parse::<i64>())
+                    .collect::<Result<Vec<_>, _>>();
+                match values {
+                    Ok(values) => {
+                        for value in values {
+                            self.machine.add_input_borrowing(value);
+                        }
+                        self.pending_io()
+                    }
+                    Err(_) => "Usage: input <value>...".to_string(),
+                }
+            }
+            "a" | "ascii" => {
+                let line = command[name.len()..].trim_start();
+                for byte in line.bytes() {
+                    self.machine.add_input_borrowing(byte as i64);
+                }
+                self.machine.add_input_borrowing(10);
+                self.pending_io()
+            }
+            _ => format!("Unknown command `{}`, try help", name),
+        };
+
+        Some(text)
+    }
+
+    pub fn repl<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        writeln!(output, "{}", self.current_instruction())?;
+        write!(output, "(icdb) ")?;
+        output.flush()?;
+
+        for line in input.lines() {
+            match self.execute(&line?) {
+                Some(text) => {
+                    if !text.is_empty() {
+                        writeln!(output, "{}", text)?;
+                    }
+                }
+                None => break,
+            }
+            write!(output, "(icdb) ")?;
+            output.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+const HELP: &str = "\
+step [n]             execute n instructions (s)
+continue             run until a breakpoint, watchpoint or io break (c)
+break [addr]         break before executing addr (b)
+watch <addr>         break when the value at addr changes (w)
+delete <addr>        remove the breakpoint or watchpoint at addr
+break-input [cond]   break before reading an input: off, any or a value (bi)
+break-output [cond]  break after an output: off, any or a value (bo)
+list                 show breakpoints, watchpoints and io breaks (l)
+regs                 show index, relative offset, status and steps (r)
+budget [n|off]       limit the total number of executed instructions
+mem [start] [n]      dump n memory cells (m)
+disasm [start] [n]   disassemble n instructions (d)
+io                   show pending inputs and outputs
+input <v>...         queue input values (i)
+ascii <text>         queue a line of text followed by a newline (a)
+quit                 leave the debugger (q)";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sums as many inputs as [18] says into [19], then outputs it
+    fn sum() -> Debugger {
+        Debugger::new(Intcode::new(vec![
+            109, 20, 203, 0, 201, 0, 19, 19, 1001, 18, -1, 18, 1005, 18, 2, 4, 19, 99, 3, 0, 0,
+        ]))
+    }
+
+    #[test]
+    fn breakpoints_stop_before_the_instruction() {
+        let mut debugger = sum();
+        debugger.execute("input 1 2 4");
+        debugger.add_breakpoint(15);
+
+        assert_eq!(debugger.resume(), StopReason::Breakpoint(15));
+        assert_eq!(debugger.machine.memory()[19], 7);
+        assert!(debugger.machine.peek_outputs().is_empty());
+        assert_eq!(debugger.resume(), StopReason::Halted);
+        assert_eq!(debugger.machine.get_outputs(), vec![7]);
+    }
+
+    #[test]
+    fn watchpoints_report_the_change() {
+        let mut debugger = sum();
+        debugger.execute("input 5 1 1");
+        debugger.add_watchpoint(19);
+
+        assert_eq!(
+            debugger.resume(),
+            StopReason::Watchpoint {
+                address: 19,
+                old: 0,
+                new: 5
+            }
+        );
+        assert_eq!(debugger.machine.index(), 8);
+        assert!(debugger.remove(19));
+        assert!(!debugger.remove(19));
+    }
+
+    #[test]
+    fn io_breaks() {
+        let mut debugger = sum();
+        debugger.input_break = IoBreak::Any;
+        assert_eq!(debugger.resume(), StopReason::Input(None));
+        assert_eq!(debugger.resume(), StopReason::Waiting);
+
+        debugger.input_break = IoBreak::Off;
+        debugger.output_break = IoBreak::Value(6);
+        debugger.execute("i 1 2 3");
+        assert_eq!(debugger.resume(), StopReason::Output(6));
+    }
+
+    #[test]
+    fn repl_session() {
+        let mut output = vec![];
+        sum()
+            .repl(
+                "b 15\ni 3 3 3\nc\nm 18 2\nfoo\nq\nc\n".as_bytes(),
+                &mut output,
+            )
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.starts_with("0000: ARB #20\n(icdb) Breakpoint set at 15\n"));
+        assert!(output.contains("Breakpoint at 15\n0015: OUT [19]\n"));
+        assert!(output.contains("0018:     0     9\n"));
+        assert!(output.contains("Unknown command `foo`, try help"));
+        // Nothing runs after quit
+        assert!(output.ends_with("(icdb) "));
+        assert_eq!(output.matches("Breakpoint at").count(), 1);
+    }
+
+    #[test]
+    fn memory_dumps_stop_at_the_end_of_memory() {
+        let mut debugger = Debugger::new(Intcode::new(vec![1, 0, 0, 3, 99]));
+
+        assert_eq!(debugger.execute("mem 3 100").unwrap(), "0003:     3    99");
+        assert_eq!(
+            debugger.execute("mem 18446744073709551615 2").unwrap(),
+            "Address 18446744073709551615 is past the end of memory (5 cells)"
+        );
+        assert_eq!(
+            debugger.execute("mem 1 18446744073709551615").unwrap(),
+            "0001:     0     0     3    99"
+        );
+        assert_eq!(
+            debugger.execute("disasm 0 18446744073709551615").unwrap(),
+            "0000: ADD [0], [0], [3]\n0004: HLT"
+        );
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+
+use super::intcode::{self, Opcode, ParameterMode};
+
+// Data cells are grouped by this many values on each `db` line
+const DATA_PER_LINE: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub address: usize,
+    pub opcode: Opcode,
+    pub parameters: Vec<(ParameterMode, i64)>,
+}
+
+impl Instruction {
+    pub fn next_address(&self) -> usize {
+        self.address + 1 + self.parameters.len()
+    }
+
+    fn is_jump(&self) -> bool {
+        self.opcode == Opcode::JumpIfTrue || self.opcode == Opcode::JumpIfFalse
+    }
+
+    // A jump on a constant condition never falls through to the next instruction
+    fn is_unconditional_jump(&self) -> bool {
+        match (self.opcode, self.parameters.first()) {
+            (Opcode::JumpIfTrue, Some((ParameterMode::Immediate, value))) => *value != 0,
+            (Opcode::JumpIfFalse, Some((ParameterMode::Immediate, value))) => *value == 0,
+            _ => false,
+        }
+    }
+
+    // Only immediate targets can be followed statically
+    fn jump_target(&self) -> Option<usize> {
+        if !self.is_jump() {
+            return None;
+        }
+        match self.parameters[1] {
+            (ParameterMode::Immediate, target) if target >= 0 => Some(target as usize),
+            _ => None,
+        }
+    }
+
+    // Immediate operands are only shown as labels when they are jump targets or return pointers
+    fn format(
+        &self,
+        labels: &BTreeMap<usize, String>,
+        pointers: &HashSet<(usize, usize)>,
+    ) -> String {
+        let parameters = self
+            .parameters
+            .iter()
+            .enumerate()
+            .map(|(index, (mode, value))| {
+                let is_code_pointer =
+                    (self.is_jump() && index == 1) || pointers.contains(&(self.address, index));
+                match (mode, labels.get(&(*value as usize))) {
+                    (ParameterMode::Immediate, Some(label)) if is_code_pointer && *value >= 0 => {
+                        format!("#{}", label)
+                    }
+                    _ => format_parameter(*mode, *value),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if parameters.is_empty() {
+            self.opcode.mnemonic().to_string()
+        } else {
+            format!("{} {}", self.opcode.mnemonic(), parameters.join(", "))
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.format(&BTreeMap::new(), &HashSet::new()))
+    }
+}
+
+pub fn format_parameter(mode: ParameterMode, value: i64) -> String {
+    match mode {
+        ParameterMode::Position => format!("[{}]", value),
+        ParameterMode::Immediate => format!("#{}", value),
+        ParameterMode::Relative if value < 0 => format!("[rb{}]", value),
+        ParameterMode::Relative => format!("[rb+{}]", value),
+    }
+}
+
+// Decodes the instruction starting at address, None if the cells there can't form a valid one
+pub fn decode_at(memory: &[i64], address: usize) -> Option<Instruction> {
+    let (opcode, modes) = intcode::decode(*memory.get(address)?)?;
+    let count = opcode.parameter_count();
+
+    if address + count >= memory.len() {
+        return None;
+    }
+    if opcode.writes_memory() && modes[count - 1] == ParameterMode::Immediate {
+        return None;
+    }
+
+    Some(Instruction {
+        address,
+        opcode,
+        parameters: (0..count)
+            .map(|index| (modes[index], memory[address + 1 + index]))
+            .collect(),
+    })
+}
+
+// Instructions by address, labelled addresses and the operands resolved as return pointers
+type Code = (
+    BTreeMap<usize, Instruction>,
+    BTreeSet<usize>,
+    HashSet<(usize, usize)>,
+);
+
+fn label_name(address: usize) -> String {
+    format!("L{:04}", address)
+}
+
+// Code is whatever is reachable from address 0 by following the statically known jumps.
+// Calls push their return address as a constant then jump away unconditionally,
+// so constants pointing right after such a jump are followed too, and reported as
+// (instruction address, parameter index) pairs.
+fn find_code(memory: &[i64]) -> Code {
+    let mut instructions: BTreeMap<usize, Instruction> = BTreeMap::new();
+    let mut is_code = vec![false; memory.len()];
+    let mut labels = BTreeSet::new();
+    let mut return_points = HashSet::new();
+    let mut pointers = HashSet::new();
+    let mut visited_entries = HashSet::new();
+    let mut entries = vec![0];
+
+    loop {
+        while let Some(entry) = entries.pop() {
+            if !visited_entries.insert(entry) {
+                continue;
+            }
+
+            let mut address = entry;
+            while !instructions.contains_key(&address) {
+                let instruction = match decode_at(memory, address) {
+                    Some(instruction) => instruction,
+                    None => break,
+                };
+                let next = instruction.next_address();
+                if is_code[address..next].iter().any(|cell| *cell) {
+                    break;
+                }
+                for cell in is_code[address..next].iter_mut() {
+                    *cell = true;
+                }
+
+                if let Some(target) = instruction.jump_target() {
+                    if target < memory.len() {
+                        labels.insert(target);
+                        entries.push(target);
+                    }
+                }
+
+                let stop =
+                    instruction.opcode == Opcode::Halt || instruction.is_unconditional_jump();
+                if instruction.is_unconditional_jump() {
+                    return_points.insert(next);
+                }
+
+                instructions.insert(address, instruction);
+                if stop {
+                    break;
+                }
+                address = next;
+            }
+        }
+
+        let found = instructions
+            .values()
+            .filter(|instruction| {
+                instruction.opcode == Opcode::Add || instruction.opcode == Opcode::Mul
+            })
+            .flat_map(|instruction| {
+                instruction
+                    .parameters
+                    .iter()
+                    .enumerate()
+                    .map(move |(index, parameter)| (instruction.address, index, *parameter))
+            })
+            .filter(|(_, _, (mode, value))| *mode == ParameterMode::Immediate && *value >= 0)
+            .filter(|(address, index, (_, value))| {
+                return_points.contains(&(*value as usize))
+                    && !pointers.contains(&(*address, *index))
+            })
+            .map(|(address, index, (_, value))| (address, index, value as usize))
+            .collect::<Vec<_>>();
+
+        if found.is_empty() {
+            break;
+        }
+
+        for (address, index, pointer) in found {
+            pointers.insert((address, index));
+            labels.insert(pointer);
+            entries.push(pointer);
+        }
+    }
+
+    (instructions, labels, pointers)
+}
+
+// Produces a listing like `0012: ADD [rb+3], #5, [120]`, jump targets get their own `L0042:` line
+pub fn disassemble(memory: &[i64]) -> String {
+    let (instructions, labels, pointers) = find_code(memory);
+    let labels = labels
+        .into_iter()
+        .map(|address| (address, label_name(address)))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut lines = vec![];
+    let mut address = 0;
+
+    while address < memory.len() {
+        if let Some(label) = labels.get(&address) {
+            lines.push(format!("{}:", label));
+        }
+
+        if let Some(instruction) = instructions.get(&address) {
+            lines.push(format!(
+                "{:04}: {}",
+                address,
+                instruction.format(&labels, &pointers)
+            ));
+            address = instruction.next_address();
+        } else {
+            let start = address;
+            let mut values = vec![];
+            while address < memory.len()
+                && values.len() < DATA_PER_LINE
+                && !instructions.contains_key(&address)
+                && (address == start || !labels.contains_key(&address))
+            {
+                values.push(memory[address].to_string());
+                address += 1;
+            }
+            lines.push(format!("{:04}: db {}", start, values.join(", ")));
+        }
+    }
+
+    let mut listing = lines.join("\n");
+    listing.push('\n');
+    listing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_and_data_are_told_apart() {
+        let memory = [
+            109, 20, 203, 0, 201, 0, 19, 19, 1001, 18, -1, 18, 1005, 18, 2, 4, 19, 99, 3, 0, 0,
+        ];
+
+        assert_eq!(
+            disassemble(&memory),
+            "\
+0000: ARB #20
+L0002:
+0002: IN [rb+0]
+0004: ADD [rb+0], [19], [19]
+0008: ADD [18], #-1, [18]
+0012: JNZ [18], #L0002
+0015: OUT [19]
+0017: HLT
+0018: db 3, 0, 0
+"
+        );
+    }
+
+    #[test]
+    fn return_addresses_are_followed() {
+        // Jumps over the code at 3, whose address is then stored as if for a call
+        let memory = [1105, 1, 7, 5, 6, 7, 8, 1101, 3, 0, 3, 99];
+
+        assert_eq!(
+            disassemble(&memory),
+            "\
+0000: JNZ #1, #L0007
+L0003:
+0003: JNZ [6], [7]
+0006: db 8
+L0007:
+0007: ADD #L0003, #0, [3]
+0011: HLT
+"
+        );
+    }
+
+    #[test]
+    fn other_constants_are_not_labelled() {
+        // 7 is stored as a plain number that happens to be a jump target
+        let memory = [1101, 7, 0, 11, 1005, 11, 7, 99, 0, 0, 0, 0];
+
+        assert_eq!(
+            disassemble(&memory),
+            "\
+0000: ADD #7, #0, [11]
+0004: JNZ [11], #L0007
+L0007:
+0007: HLT
+0008: db 0, 0, 0, 0
+"
+        );
+    }
+
+    #[test]
+    fn invalid_cells_are_not_instructions() {
+        assert_eq!(decode_at(&[11101, 1, 1, 0], 0), None);
+        assert_eq!(decode_at(&[1, 0, 0], 0), None);
+        assert_eq!(decode_at(&[42], 0), None);
+        assert_eq!(decode_at(&[99], 1), None);
+        assert_eq!(decode_at(&[204, -3], 0).unwrap().to_string(), "OUT [rb-3]");
+    }
+}
@@ -1,71 +1,405 @@
+use std::collections::{HashMap, VecDeque};
 use std::error::Error;
-use std::fs;
 use std::path::Path;
 
 use std::io::{self};
 
+use super::answer::Answer;
+use super::ascii::AsciiIntcode;
+use super::input::read_input;
 use super::intcode;
+use super::program;
+use super::snapshot;
 
-// Yeah, I've basically played the game instead of trying to solve it :/
-pub fn first_star() -> Result<(), Box<dyn Error + 'static>> {
-    let mut drone =
-        intcode::Intcode::new_with_path(fs::read_to_string(Path::new("./data/finalday.txt"))?);
+const PROMPT: &str = "Command?";
 
-    loop {
-        drone = drone.run();
+// Some items end the game or never give the prompt back once taken,
+// a copy of the droid tries them first and gives up after that many instructions
+const TRY_BUDGET: u64 = 1_000_000;
 
-        let mut output = drone.get_outputs();
-        output.reverse(); 
+#[derive(Clone, Debug, Default)]
+pub struct Room {
+    pub name: String,
+    pub doors: Vec<String>,
+    pub items: Vec<String>,
+}
 
-        let output = output
-            .into_iter()
-            .map(|x| (x as u8) as char)
-            .collect::<String>();
-        let output = output.trim();
+// Rooms described in the droid's output, in order. Being ejected from a room prints two of them.
+pub fn parse_rooms(text: &str) -> Vec<Room> {
+    let mut rooms: Vec<Room> = vec![];
+    let mut list = "";
 
-        println!("{}", output);
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if line.starts_with("== ") && line.ends_with(" ==") {
+            rooms.push(Room {
+                name: line[3..line.len() - 3].to_string(),
+                ..Room::default()
+            });
+            list = "";
+        } else if let Some(entry) = line.strip_prefix("- ") {
+            if let Some(room) = rooms.last_mut() {
+                match list {
+                    "Doors here lead:" => room.doors.push(entry.to_string()),
+                    "Items here:" => room.items.push(entry.to_string()),
+                    _ => {}
+                }
+            }
+        } else {
+            list = line;
+        }
+    }
 
-        match drone.status {
-            intcode::CompStatus::Halted => {
-                println!("Drone halted!");
-                break;
+    rooms
+}
+
+fn opposite(direction: &str) -> Result<&'static str, Box<dyn Error + 'static>> {
+    match direction {
+        "north" => Ok("south"),
+        "south" => Ok("north"),
+        "east" => Ok("west"),
+        "west" => Ok("east"),
+        _ => Err(format!("Unknown direction: {}", direction).into()),
+    }
+}
+
+pub fn password(text: &str) -> Option<String> {
+    let digits = text
+        .split("typing ")
+        .nth(1)?
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect::<String>();
+    if digits.is_empty() {
+        None
+    } else {
+        Some(digits)
+    }
+}
+
+struct Explorer {
+    drone: AsciiIntcode,
+    // Room name -> direction -> room name
+    map: HashMap<String, HashMap<String, String>>,
+    carried: Vec<String>,
+    // Room and direction leading to the pressure-sensitive floor
+    floor: Option<(String, String)>,
+}
+
+impl Explorer {
+    fn command(&mut self, command: &str) -> Result<String, Box<dyn Error + 'static>> {
+        self.drone.send_command(command);
+        self.drone.read_until_prompt(PROMPT)
+    }
+
+    // A copy of the droid takes the item and walks through a door with it,
+    // the item is safe if the copy is still answering and moved
+    fn is_safe(&self, item: &str, door: &str) -> bool {
+        let mut copy = self.drone.clone();
+        let budget = copy.machine().steps() + TRY_BUDGET;
+        copy.machine_mut().set_budget(Some(budget));
+        copy.send_command(&format!("take {}", item));
+        copy.send_command(door);
+
+        match (
+            copy.read_until_prompt(PROMPT),
+            copy.read_until_prompt(PROMPT),
+        ) {
+            (Ok(taken), Ok(moved)) => {
+                taken.contains("You take the") && !parse_rooms(&moved).is_empty()
+            }
+            _ => false,
+        }
+    }
+
+    // Depth first, the droid is back in the room when this returns
+    fn explore(&mut self, room: &Room) -> Result<(), Box<dyn Error + 'static>> {
+        if let Some(door) = room.doors.first() {
+            for item in &room.items {
+                if self.is_safe(item, door) {
+                    self.command(&format!("take {}", item))?;
+                    self.carried.push(item.clone());
+                }
             }
-            intcode::CompStatus::Error => {
-                panic!("Something went wrong?");
+        }
+
+        for door in &room.doors {
+            if self.map[&room.name].contains_key(door) {
+                continue;
             }
-            _ => {}
+
+            let rooms = parse_rooms(&self.command(door)?);
+            let next = match rooms.first() {
+                Some(next) => next.clone(),
+                None => return Err(format!("Going {} from {} led nowhere", door, room.name).into()),
+            };
+            self.map
+                .get_mut(&room.name)
+                .unwrap()
+                .insert(door.clone(), next.name.clone());
+
+            if rooms.len() > 1 {
+                // Ejected back here, the floor is only worth coming back to with the right items
+                self.floor = Some((room.name.clone(), door.clone()));
+                continue;
+            }
+
+            let back = opposite(door)?;
+            let visited = self.map.contains_key(&next.name);
+            self.map
+                .entry(next.name.clone())
+                .or_default()
+                .insert(back.to_string(), room.name.clone());
+            if !visited {
+                self.explore(&next)?;
+            }
+            self.command(back)?;
         }
 
-        let mut buffer = String::new();
-        match io::stdin().read_line(&mut buffer) {
-            Ok(_) => {
-                let buffer = buffer.trim();
-                match buffer {
-                    "exit" => {
-                        println!("Exiting adventure game");
-                        break;
+        Ok(())
+    }
+
+    fn path(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        let mut previous: HashMap<&str, (&str, &str)> = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(from);
+
+        while let Some(room) = queue.pop_front() {
+            if room == to {
+                let mut path = vec![];
+                let mut room = to;
+                while room != from {
+                    let (before, door) = previous[room];
+                    path.push(door.to_string());
+                    room = before;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            // Rooms only seen through a door, like the floor, lead nowhere
+            if let Some(doors) = self.map.get(room) {
+                for (door, next) in doors {
+                    if next != from && !previous.contains_key(next.as_str()) {
+                        previous.insert(next, (room, door));
+                        queue.push_back(next);
                     }
-                    "clear" => {
-                        print!("\x1B[2J");
-                        continue;
+                }
+            }
+        }
+
+        None
+    }
+
+    // Tries every set of carried items on the floor, one take or drop between two tries
+    fn crack(&mut self, door: &str) -> Result<String, Box<dyn Error + 'static>> {
+        let items = self.carried.clone();
+        for item in &items {
+            self.command(&format!("drop {}", item))?;
+        }
+
+        let mut held = 0u64;
+        for attempt in 0..1u64 << items.len() {
+            let wanted = attempt ^ (attempt >> 1);
+            for (index, item) in items.iter().enumerate() {
+                match ((held >> index) & 1, (wanted >> index) & 1) {
+                    (0, 1) => self.command(&format!("take {}", item))?,
+                    (1, 0) => self.command(&format!("drop {}", item))?,
+                    _ => continue,
+                };
+            }
+            held = wanted;
+
+            self.drone.send_command(door);
+            let text = self.drone.read_all()?;
+            if let Some(password) = password(&text) {
+                return Ok(password);
+            }
+            if self.drone.status() == intcode::CompStatus::Halted {
+                return Err(format!("The droid stopped on the floor:\n{}", text).into());
+            }
+        }
+
+        Err("No set of items gets through the pressure-sensitive floor".into())
+    }
+}
+
+pub fn solve(program: String) -> Result<String, Box<dyn Error + 'static>> {
+    let mut drone = AsciiIntcode::new(intcode::Intcode::new(program::load(&program)?));
+    let start = parse_rooms(&drone.read_until_prompt(PROMPT)?)
+        .pop()
+        .ok_or("The droid didn't say where it landed")?;
+
+    let mut explorer = Explorer {
+        drone,
+        map: HashMap::new(),
+        carried: vec![],
+        floor: None,
+    };
+    explorer.map.insert(start.name.clone(), HashMap::new());
+    explorer.explore(&start)?;
+
+    let (checkpoint, door) = explorer
+        .floor
+        .clone()
+        .ok_or("The droid never found the pressure-sensitive floor")?;
+    let path = explorer
+        .path(&start.name, &checkpoint)
+        .ok_or_else(|| format!("No way back to the {}", checkpoint))?;
+    for step in path {
+        explorer.command(&step)?;
+    }
+
+    explorer.crack(&door)
+}
+
+pub fn first_star() -> Result<Answer, Box<dyn Error + 'static>> {
+    Ok(Answer::Text(solve(read_input("finalday")?)?))
+}
+
+// The game played by hand, that's how the first star was found before the solver
+pub fn play() -> Result<(), Box<dyn Error + 'static>> {
+    let mut drone = AsciiIntcode::new(intcode::Intcode::new(program::load(&read_input(
+        "finalday",
+    )?)?));
+
+    loop {
+        println!("{}", drone.read_all()?.trim());
+
+        if drone.status() == intcode::CompStatus::Halted {
+            println!("Drone halted!");
+            break;
+        }
+
+        let mut buffer = String::new();
+        // The end of the input leaves the game like exit does
+        if io::stdin().read_line(&mut buffer)? == 0 {
+            break;
+        }
+        let buffer = buffer.trim();
+        match buffer {
+            "exit" => {
+                println!("Exiting adventure game");
+                break;
+            }
+            "clear" => {
+                print!("\x1B[2J");
+                continue;
+            }
+            // Checkpoints: `save <file>` writes the whole machine, `load <file>` resumes from one
+            command if command.starts_with("save ") => {
+                match snapshot::save(drone.machine(), Path::new(command[5..].trim())) {
+                    Ok(()) => println!("Checkpoint saved"),
+                    Err(error) => println!("Could not save: {}", error),
+                }
+                continue;
+            }
+            command if command.starts_with("load ") => {
+                match snapshot::load(Path::new(command[5..].trim())) {
+                    Ok(loaded) => {
+                        drone = AsciiIntcode::new(loaded);
+                        println!("Checkpoint loaded, enter a command");
                     }
-                    _ => {}
+                    Err(error) => println!("Could not load: {}", error),
                 }
-                // Must use trim_end or the line ending mess up with the parsing function
-                let mut inputs = buffer
-                    .chars()
-                    .map(|x| (x as u8) as i64)
-                    .collect::<Vec<i64>>();
-                inputs.push(10);
-                drone.set_inputs(&inputs);
+                continue;
             }
-            Err(error) => panic!("Error: {}, input went wrong", error),
+            _ => {}
         }
+        drone.send_command(buffer);
     }
 
     Ok(())
 }
 
-pub fn second_star() -> Result<(), Box<dyn Error + 'static>> {
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rooms_are_parsed() {
+        let text = "
+
+== Hull Breach ==
+You got in through a hole in the floor here.
+
+Doors here lead:
+- north
+- west
+
+Items here:
+- mug
+
+Command?";
+        let rooms = parse_rooms(text);
+
+        assert_eq!(rooms.len(), 1);
+        assert_eq!(rooms[0].name, "Hull Breach");
+        assert_eq!(rooms[0].doors, vec!["north", "west"]);
+        assert_eq!(rooms[0].items, vec!["mug"]);
+    }
+
+    #[test]
+    fn ejection_prints_two_rooms() {
+        let text = "== Pressure-Sensitive Floor ==
+Alert! Droids on this ship are lighter than the detected value!
+
+== Security Checkpoint ==
+Doors here lead:
+- north
+- south
+";
+        let names = parse_rooms(text)
+            .into_iter()
+            .map(|room| room.name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["Pressure-Sensitive Floor", "Security Checkpoint"]
+        );
+    }
+
+    #[test]
+    fn password_follows_typing() {
+        let text =
+            "You should be able to get in by typing 2424308736 on the keypad at the main airlock.";
+        assert_eq!(password(text), Some("2424308736".to_string()));
+        assert_eq!(password("Command?"), None);
+    }
+
+    #[test]
+    fn paths_go_around_rooms_never_entered() {
+        let mut explorer = Explorer {
+            drone: AsciiIntcode::new(intcode::Intcode::new(vec![99])),
+            map: HashMap::new(),
+            carried: vec![],
+            floor: None,
+        };
+        let mut door = |from: &str, direction: &str, to: &str| {
+            explorer
+                .map
+                .entry(from.to_string())
+                .or_default()
+                .insert(direction.to_string(), to.to_string());
+        };
+        door("Hull Breach", "west", "Pressure-Sensitive Floor");
+        door("Hull Breach", "north", "Hallway");
+        door("Hallway", "south", "Hull Breach");
+        door("Hallway", "north", "Security Checkpoint");
+        door("Security Checkpoint", "south", "Hallway");
+
+        assert_eq!(
+            explorer.path("Hull Breach", "Security Checkpoint"),
+            Some(vec!["north".to_string(), "north".to_string()])
+        );
+        assert_eq!(
+            explorer.path("Security Checkpoint", "Hull Breach"),
+            Some(vec!["south".to_string(), "south".to_string()])
+        );
+        assert_eq!(explorer.path("Hull Breach", "Engineering"), None);
+    }
+
+    #[test]
+    fn directions_have_opposites() {
+        assert_eq!(opposite("north").unwrap(), "south");
+        assert!(opposite("up").is_err());
+    }
 }
@@ -0,0 +1,66 @@
+use std::collections::{HashMap, VecDeque};
+
+// (x, y) with y growing downwards, as the maps are printed
+pub type Coordinate = (i32, i32);
+
+// Up, right, down, left
+pub const DIRECTIONS: [Coordinate; 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+pub fn neighbours((x, y): Coordinate) -> [Coordinate; 4] {
+    DIRECTIONS.map(|(dx, dy)| (x + dx, y + dy))
+}
+
+// Steps from start to every cell reachable by moving through open cells, start included
+pub fn distances<F>(start: Coordinate, is_open: F) -> HashMap<Coordinate, usize>
+where
+    F: Fn(Coordinate) -> bool,
+{
+    let mut distances = HashMap::new();
+    let mut queue = VecDeque::new();
+    distances.insert(start, 0);
+    queue.push_back(start);
+
+    while let Some(coordinate) = queue.pop_front() {
+        let distance = distances[&coordinate] + 1;
+        for neighbour in neighbours(coordinate) {
+            if !distances.contains_key(&neighbour) && is_open(neighbour) {
+                distances.insert(neighbour, distance);
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neighbours_go_around_clockwise() {
+        assert_eq!(neighbours((0, 0)), [(0, -1), (1, 0), (0, 1), (-1, 0)]);
+    }
+
+    #[test]
+    fn distances_go_around_walls() {
+        let map = "\
+#####
+#..##
+##.##
+#...#
+#####";
+        let open = |(x, y): Coordinate| {
+            map.lines()
+                .nth(y as usize)
+                .and_then(|line| line.chars().nth(x as usize))
+                == Some('.')
+        };
+        let distances = distances((1, 1), open);
+
+        assert_eq!(distances.len(), 6);
+        assert_eq!(distances[&(1, 1)], 0);
+        assert_eq!(distances[&(1, 3)], 4);
+        assert_eq!(distances[&(3, 3)], 4);
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use super::verify::ANSWERS;
+
+// Looked at when no directory is given on the command line
+pub const DIR_VARIABLE: &str = "AOC_INPUT_DIR";
+const DEFAULT_DIR: &str = "./data";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    File(PathBuf),
+    Stdin,
+}
+
+impl From<&str> for Source {
+    fn from(path: &str) -> Self {
+        match path {
+            "-" => Source::Stdin,
+            path => Source::File(PathBuf::from(path)),
+        }
+    }
+}
+
+// An input called `name` comes from, in order: its own override, the override of every input,
+// <dir>/<name>.txt with the given directory, then $AOC_INPUT_DIR, then ./data.
+// The answers of verify are not a puzzle input, the override of every input leaves them alone.
+#[derive(Clone, Debug, Default)]
+pub struct Inputs {
+    pub dir: Option<PathBuf>,
+    pub all: Option<Source>,
+    pub by_name: HashMap<String, Source>,
+}
+
+impl Inputs {
+    pub fn resolve(&self, name: &str) -> Source {
+        let all = self.all.as_ref().filter(|_| name != ANSWERS);
+        if let Some(source) = self.by_name.get(name).or(all) {
+            return source.clone();
+        }
+
+        let dir = self
+            .dir
+            .clone()
+            .or_else(|| env::var_os(DIR_VARIABLE).map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_DIR));
+        Source::File(dir.join(format!("{}.txt", name)))
+    }
+}
+
+static INPUTS: Mutex<Option<Inputs>> = Mutex::new(None);
+// Stdin can only be read once, every star asking for it gets the same text
+static STDIN: Mutex<Option<String>> = Mutex::new(None);
+
+pub fn configure(inputs: Inputs) {
+    *INPUTS.lock().unwrap() = Some(inputs);
+}
+
+fn read_stdin() -> Result<String, Box<dyn Error + 'static>> {
+    let mut stdin = STDIN.lock().unwrap();
+    if let Some(text) = &*stdin {
+        return Ok(text.clone());
+    }
+
+    let mut text = String::new();
+    io::stdin()
+        .read_to_string(&mut text)
+        .map_err(|error| format!("Could not read stdin: {}", error))?;
+    *stdin = Some(text.clone());
+    Ok(text)
+}
+
+pub fn read_input(name: &str) -> Result<String, Box<dyn Error + 'static>> {
+    let source = match &*INPUTS.lock().unwrap() {
+        Some(inputs) => inputs.resolve(name),
+        None => Inputs::default().resolve(name),
+    };
+
+    match source {
+        Source::Stdin => read_stdin(),
+        Source::File(path) => fs::read_to_string(&path)
+            .map_err(|error| format!("Could not read {}: {}", path.display(), error).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_come_first() {
+        let mut inputs = Inputs {
+            dir: Some(PathBuf::from("inputs")),
+            ..Default::default()
+        };
+        assert_eq!(
+            inputs.resolve("day18_b"),
+            Source::File(PathBuf::from("inputs/day18_b.txt"))
+        );
+
+        inputs.all = Some("-".into());
+        inputs.by_name.insert("day18_b".to_string(), "b.txt".into());
+        assert_eq!(inputs.resolve("day18"), Source::Stdin);
+        assert_eq!(
+            inputs.resolve("day18_b"),
+            Source::File(PathBuf::from("b.txt"))
+        );
+    }
+
+    #[test]
+    fn answers_ignore_the_override_of_every_input() {
+        let mut inputs = Inputs {
+            dir: Some(PathBuf::from("inputs")),
+            all: Some("day1.txt".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            inputs.resolve(ANSWERS),
+            Source::File(PathBuf::from("inputs/answers.txt"))
+        );
+
+        inputs.by_name.insert(ANSWERS.to_string(), "-".into());
+        assert_eq!(inputs.resolve(ANSWERS), Source::Stdin);
+    }
+}
@@ -1,12 +1,125 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::BufRead;
+use std::sync::mpsc::{Receiver, Sender};
+
+use super::profiler::{self, Profile};
+use super::snapshot::Snapshot;
+
+// Inputs and outputs default to plain fifo queues, other sources and sinks can be plugged with with_io
 #[derive(Clone, Debug)]
-pub struct Intcode {
+pub struct Intcode<I = VecDeque<i64>, O = VecDeque<i64>> {
     memory: Vec<i64>,
     index: usize,
-    inputs: Vec<i64>,
+    inputs: I,
     relative_offset: i64,
     pub output: i64,
-    pub outputs: Vec<i64>,
+    pub outputs: O,
     pub status: CompStatus,
+    // Boxed so a machine that isn't profiled only pays for a null pointer
+    profile: Option<Box<Profile>>,
+    // Decoded instruction per address, only kept once enable_decode_cache has been called
+    decoded: Option<Vec<Option<Decoded>>>,
+    // Instructions executed so far, the machine stops with OutOfBudget once it reaches the budget
+    steps: u64,
+    budget: Option<u64>,
+}
+
+// Called on opcode 3, returning None puts the machine in the Waiting state until the next run
+pub trait InputSource {
+    fn next_input(&mut self) -> Option<i64>;
+}
+
+// Called on opcode 4 with every value the program outputs
+pub trait OutputSink {
+    fn push_output(&mut self, value: i64);
+}
+
+impl InputSource for VecDeque<i64> {
+    fn next_input(&mut self) -> Option<i64> {
+        self.pop_front()
+    }
+}
+
+impl OutputSink for VecDeque<i64> {
+    fn push_output(&mut self, value: i64) {
+        self.push_back(value);
+    }
+}
+
+pub struct FnInput<F>(pub F);
+
+impl<F: FnMut() -> Option<i64>> InputSource for FnInput<F> {
+    fn next_input(&mut self) -> Option<i64> {
+        (self.0)()
+    }
+}
+
+impl<F> fmt::Debug for FnInput<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("FnInput")
+    }
+}
+
+pub struct FnOutput<F>(pub F);
+
+impl<F: FnMut(i64)> OutputSink for FnOutput<F> {
+    fn push_output(&mut self, value: i64) {
+        (self.0)(value);
+    }
+}
+
+impl<F> fmt::Debug for FnOutput<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("FnOutput")
+    }
+}
+
+// Feeds a whole line (newline included) as ascii codes each time the previous one has been consumed
+#[derive(Debug)]
+pub struct AsciiInput<R> {
+    reader: R,
+    pending: VecDeque<i64>,
+}
+
+impl<R: BufRead> AsciiInput<R> {
+    pub fn new(reader: R) -> Self {
+        AsciiInput {
+            reader,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<R: BufRead> InputSource for AsciiInput<R> {
+    fn next_input(&mut self) -> Option<i64> {
+        if self.pending.is_empty() {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line) {
+                Ok(0) | Err(_) => return None,
+                Ok(_) => {
+                    self.pending
+                        .extend(line.trim_end().bytes().map(|byte| byte as i64));
+                    self.pending.push_back(10);
+                }
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+// Blocks until a value is sent, a closed channel is treated as "no more input"
+impl InputSource for Receiver<i64> {
+    fn next_input(&mut self) -> Option<i64> {
+        self.recv().ok()
+    }
+}
+
+// A closed channel means nobody listens anymore, so the value is simply dropped
+impl OutputSink for Sender<i64> {
+    fn push_output(&mut self, value: i64) {
+        let _ = self.send(value);
+    }
 }
 
 trait MemExpand {
@@ -31,122 +144,504 @@ impl MemExpand for Vec<i64> {
     }
 }
 
-type ParameterFlags = (ParameterMode, ParameterMode, ParameterMode);
+type ParameterFlags = [ParameterMode; 3];
+type Decoded = (Option<Opcode>, ParameterFlags);
+type Operands = (Option<i64>, Option<i64>, Option<usize>);
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum CompStatus {
     Running,
     Waiting,
     Halted,
-    Error,
+    // The step budget is spent, raising it or calling run_for resumes the machine
+    OutOfBudget,
+    Error(IntcodeError),
+}
+
+// Every variant carries the address of the faulty instruction so the caller can point at it
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum IntcodeError {
+    UnknownOpcode { address: usize, opcode: i64 },
+    ImmediateWrite { address: usize, instruction: i64 },
+    NegativeAddress { address: usize, target: i64 },
+    OutputOutOfBounds { address: usize, target: usize },
 }
 
-#[derive(Debug, PartialEq)]
-enum ParameterMode {
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntcodeError::UnknownOpcode { address, opcode } => {
+                write!(f, "unknown opcode {} at address {}", opcode, address)
+            }
+            IntcodeError::ImmediateWrite {
+                address,
+                instruction,
+            } => write!(
+                f,
+                "instruction {} at address {} writes through an immediate parameter",
+                instruction, address
+            ),
+            IntcodeError::NegativeAddress { address, target } => write!(
+                f,
+                "instruction at address {} uses negative address {}",
+                address, target
+            ),
+            IntcodeError::OutputOutOfBounds { address, target } => write!(
+                f,
+                "output at address {} reads address {} past the end of memory",
+                address, target
+            ),
+        }
+    }
+}
+
+impl std::error::Error for IntcodeError {}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ParameterMode {
     Position,
     Immediate,
     Relative,
 }
 
+impl ParameterMode {
+    pub fn from_digit(digit: i64) -> Option<ParameterMode> {
+        match digit {
+            0 => Some(Position),
+            1 => Some(Immediate),
+            2 => Some(Relative),
+            _ => None,
+        }
+    }
+
+    pub fn digit(self) -> i64 {
+        match self {
+            Position => 0,
+            Immediate => 1,
+            Relative => 2,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    Add,
+    Mul,
+    Input,
+    Output,
+    JumpIfTrue,
+    JumpIfFalse,
+    LessThan,
+    Equals,
+    AdjustBase,
+    Halt,
+}
+
+// Shared by the interpreter, the disassembler and the assembler so they can't disagree on the encoding
+impl Opcode {
+    pub const ALL: [Opcode; 10] = [
+        Opcode::Add,
+        Opcode::Mul,
+        Opcode::Input,
+        Opcode::Output,
+        Opcode::JumpIfTrue,
+        Opcode::JumpIfFalse,
+        Opcode::LessThan,
+        Opcode::Equals,
+        Opcode::AdjustBase,
+        Opcode::Halt,
+    ];
+
+    pub fn from_code(code: i64) -> Option<Opcode> {
+        match code {
+            1 => Some(Opcode::Add),
+            2 => Some(Opcode::Mul),
+            3 => Some(Opcode::Input),
+            4 => Some(Opcode::Output),
+            5 => Some(Opcode::JumpIfTrue),
+            6 => Some(Opcode::JumpIfFalse),
+            7 => Some(Opcode::LessThan),
+            8 => Some(Opcode::Equals),
+            9 => Some(Opcode::AdjustBase),
+            99 => Some(Opcode::Halt),
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> i64 {
+        match self {
+            Opcode::Add => 1,
+            Opcode::Mul => 2,
+            Opcode::Input => 3,
+            Opcode::Output => 4,
+            Opcode::JumpIfTrue => 5,
+            Opcode::JumpIfFalse => 6,
+            Opcode::LessThan => 7,
+            Opcode::Equals => 8,
+            Opcode::AdjustBase => 9,
+            Opcode::Halt => 99,
+        }
+    }
+
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Opcode::Add => "ADD",
+            Opcode::Mul => "MUL",
+            Opcode::Input => "IN",
+            Opcode::Output => "OUT",
+            Opcode::JumpIfTrue => "JNZ",
+            Opcode::JumpIfFalse => "JZ",
+            Opcode::LessThan => "LT",
+            Opcode::Equals => "EQ",
+            Opcode::AdjustBase => "ARB",
+            Opcode::Halt => "HLT",
+        }
+    }
+
+    pub fn parameter_count(self) -> usize {
+        match self {
+            Opcode::Add | Opcode::Mul | Opcode::LessThan | Opcode::Equals => 3,
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => 2,
+            Opcode::Input | Opcode::Output | Opcode::AdjustBase => 1,
+            Opcode::Halt => 0,
+        }
+    }
+
+    // The last parameter of these opcodes is a destination, it can't be in immediate mode
+    pub fn writes_memory(self) -> bool {
+        matches!(
+            self,
+            Opcode::Add | Opcode::Mul | Opcode::Input | Opcode::LessThan | Opcode::Equals
+        )
+    }
+}
+
+// Strict decoding of a memory cell, None when it can't be a valid instruction
+pub fn decode(instruction: i64) -> Option<(Opcode, [ParameterMode; 3])> {
+    if instruction < 0 {
+        return None;
+    }
+
+    let opcode = Opcode::from_code(instruction % 100)?;
+    let mut modes = [Position; 3];
+    let mut flags = instruction / 100;
+    for mode in modes.iter_mut() {
+        *mode = ParameterMode::from_digit(flags % 10)?;
+        flags /= 10;
+    }
+
+    if flags != 0 {
+        return None;
+    }
+
+    Some((opcode, modes))
+}
+
+// Decoding used by the interpreter, as lenient as the original one: any mode digit above 1 means relative.
+// An unknown opcode is only an error once the machine tries to execute it.
+pub fn decode_lenient(instruction: i64) -> (Option<Opcode>, [ParameterMode; 3]) {
+    if instruction < 0 {
+        return (None, [Position; 3]);
+    }
+
+    let mode = |digit| match digit {
+        0 => Position,
+        1 => Immediate,
+        _ => Relative,
+    };
+
+    (
+        Opcode::from_code(instruction % 100),
+        [
+            mode(instruction / 100 % 10),
+            mode(instruction / 1000 % 10),
+            mode(instruction / 10000 % 10),
+        ],
+    )
+}
+
 use self::CompStatus::*;
 use self::ParameterMode::*;
 
 impl Intcode {
     pub fn new(memory: Vec<i64>) -> Intcode {
+        Intcode::with_io(memory, VecDeque::new(), VecDeque::new())
+    }
+
+    // The profile, step counter and budget are not part of the machine state and are not restored
+    pub fn from_snapshot(snapshot: Snapshot) -> Intcode {
         Intcode {
-            memory,
-            index: 0,
-            inputs: vec![],
-            output: 0,
-            outputs: vec![],
-            relative_offset: 0,
-            status: Running,
+            memory: snapshot.memory,
+            index: snapshot.index,
+            inputs: snapshot.inputs.into_iter().collect(),
+            relative_offset: snapshot.relative_offset,
+            output: snapshot.output,
+            outputs: snapshot.outputs.into_iter().collect(),
+            status: snapshot.status,
+            profile: None,
+            decoded: None,
+            steps: 0,
+            budget: None,
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            memory: self.memory.clone(),
+            index: self.index,
+            relative_offset: self.relative_offset,
+            inputs: self.inputs.iter().cloned().collect(),
+            outputs: self.outputs.iter().cloned().collect(),
+            output: self.output,
+            status: self.status,
         }
     }
+}
+
+impl<O: OutputSink> Intcode<VecDeque<i64>, O> {
+    pub fn set_inputs(&mut self, inputs: &[i64]) {
+        self.inputs = inputs.iter().cloned().collect();
+        self.status = Running;
+    }
+
+    pub fn add_input(mut self, input: i64) -> Self {
+        self.add_input_borrowing(input);
+        self
+    }
+
+    pub fn add_input_borrowing(&mut self, input: i64) {
+        self.inputs.push_back(input);
 
-    pub fn new_with_path(path: String) -> Intcode {
-        let memory = prepare_memory(path);
+        if self.status == Waiting {
+            self.status = Running;
+        }
+    }
+}
+
+impl<I: InputSource> Intcode<I, VecDeque<i64>> {
+    // Outputs are returned in the order the program emitted them
+    pub fn get_outputs(&mut self) -> Vec<i64> {
+        self.outputs.drain(..).collect()
+    }
+
+    pub fn peek_outputs(&mut self) -> &VecDeque<i64> {
+        &self.outputs
+    }
+}
+
+impl<I: InputSource, O: OutputSink> Intcode<I, O> {
+    pub fn with_io(memory: Vec<i64>, inputs: I, outputs: O) -> Self {
         Intcode {
             memory,
             index: 0,
-            inputs: vec![],
+            inputs,
             output: 0,
-            outputs: vec![],
+            outputs,
             relative_offset: 0,
             status: Running,
+            profile: None,
+            decoded: None,
+            steps: 0,
+            budget: None,
         }
     }
 
-    pub fn run(mut self) -> Self {
+    // Decodes the whole memory once, later steps only look the result up.
+    // Clones share the work done here, which pays off when many copies of one program are run.
+    pub fn enable_decode_cache(&mut self) {
+        self.decoded = Some(
+            self.memory
+                .iter()
+                .map(|cell| Some(decode_lenient(*cell)))
+                .collect(),
+        );
+    }
+
+    // Counts every executed instruction from now on, trace also logs each of them with its operands
+    pub fn enable_profiling(&mut self, trace: bool) {
+        self.profile = Some(Box::new(Profile::new(trace)));
+    }
+
+    pub fn take_profile(&mut self) -> Option<Profile> {
+        self.profile.take().map(|profile| *profile)
+    }
+
+    // Read only views on the registers and memory, mostly for tooling such as the debugger
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn relative_offset(&self) -> i64 {
+        self.relative_offset
+    }
+
+    pub fn memory(&self) -> &[i64] {
+        &self.memory
+    }
+
+    pub fn inputs(&self) -> &I {
+        &self.inputs
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn budget(&self) -> Option<u64> {
+        self.budget
+    }
+
+    // Caps the total number of instructions the machine may execute, counting the ones already run
+    pub fn set_budget(&mut self, budget: Option<u64>) {
+        self.budget = budget;
+        if self.status == OutOfBudget && !self.budget_spent() {
+            self.status = Running;
+        }
+    }
+
+    fn budget_spent(&self) -> bool {
+        matches!(self.budget, Some(budget) if self.steps >= budget)
+    }
+
+    pub fn run(self) -> Self {
+        self.run_for(u64::MAX)
+    }
+
+    pub fn run_borrowing(&mut self) {
+        self.run_for_borrowing(u64::MAX);
+    }
+
+    // Executes at most `steps` more instructions, still Running afterwards becomes OutOfBudget
+    pub fn run_for(mut self, steps: u64) -> Self {
+        self.run_for_borrowing(steps);
+        self
+    }
+
+    pub fn run_for_borrowing(&mut self, steps: u64) {
+        if self.status == OutOfBudget && !self.budget_spent() {
+            self.status = Running;
+        }
+
+        let limit = self.steps.saturating_add(steps);
         while self.status == Running {
+            if self.steps >= limit {
+                self.status = OutOfBudget;
+                break;
+            }
             self.status = self.next_op();
         }
-        self
     }
 
     pub fn next_op(&mut self) -> CompStatus {
-        fn convert_to_flag(input: char) -> ParameterMode {
-            if input == '0' {
-                Position
-            } else if input == '1' {
-                Immediate
-            } else {
-                Relative
-            }
+        if self.budget_spent() {
+            return OutOfBudget;
         }
 
-        let instruction = format!("{:05}", self.memory[self.index]);
-        let mut instruction = instruction.chars();
+        let raw_instruction = self.memory.get(self.index);
+        let (opcode, parameter_flags) = self.decode(raw_instruction);
 
-        // The nth operation consume the element and the previous ones, nth(0) also shift the iterator
-        let parameter_flags = (
-            convert_to_flag(instruction.next().unwrap()),
-            convert_to_flag(instruction.next().unwrap()),
-            convert_to_flag(instruction.next().unwrap()),
-        );
+        let address = self.index;
+        let trace = match (&self.profile, opcode) {
+            (Some(profile), Some(opcode)) if profile.trace.is_some() => Some(profiler::trace_line(
+                &self.memory,
+                address,
+                self.relative_offset,
+                opcode,
+                parameter_flags,
+            )),
+            _ => None,
+        };
 
-        // Set parameter flags in the right order
-        let parameter_flags = (parameter_flags.2, parameter_flags.1, parameter_flags.0);
-
-        let opcode: String = instruction.collect();
-
-        // Possible refactoring: store functions in an hashmap with opcodes as keys
-        match opcode.as_ref() {
-            "01" => self.add(parameter_flags),
-            "02" => self.mul(parameter_flags),
-            "03" => self.use_input(parameter_flags),
-            "04" => self.output(parameter_flags),
-            "05" => self.jump_if_true(parameter_flags),
-            "06" => self.jump_if_false(parameter_flags),
-            "07" => self.less_than(parameter_flags),
-            "08" => self.equals(parameter_flags),
-            "09" => self.set_relative_offset(parameter_flags),
-            "99" => Halted,
-            _ => {
-                println!("HALT AND CATCH FIRE");
-                Error
+        let result = match opcode {
+            Some(Opcode::Add) => self.add(parameter_flags),
+            Some(Opcode::Mul) => self.mul(parameter_flags),
+            Some(Opcode::Input) => self.use_input(parameter_flags),
+            Some(Opcode::Output) => self.output(parameter_flags),
+            Some(Opcode::JumpIfTrue) => self.jump_if_true(parameter_flags),
+            Some(Opcode::JumpIfFalse) => self.jump_if_false(parameter_flags),
+            Some(Opcode::LessThan) => self.less_than(parameter_flags),
+            Some(Opcode::Equals) => self.equals(parameter_flags),
+            Some(Opcode::AdjustBase) => self.set_relative_offset(parameter_flags),
+            Some(Opcode::Halt) => Ok(Halted),
+            None => Err(IntcodeError::UnknownOpcode {
+                address: self.index,
+                opcode: raw_instruction,
+            }),
+        };
+
+        // Same rule as the profile below: a waiting input or a failed instruction didn't execute
+        if let Ok(status) = result {
+            if status != Waiting {
+                self.steps += 1;
             }
         }
+
+        if let Some(profile) = self.profile.as_mut() {
+            match (opcode, &result) {
+                // A waiting input didn't execute, it will be counted when it gets its value
+                (_, Ok(Waiting)) | (_, Err(_)) | (None, _) => {}
+                (Some(opcode), _) => {
+                    profile.record(address, opcode);
+                    if let (Some(log), Some((line, destination))) = (profile.trace.as_mut(), trace)
+                    {
+                        log.push(match destination {
+                            Some(destination) => format!(
+                                "{} -> [{}] = {}",
+                                line,
+                                destination,
+                                self.memory.get(destination)
+                            ),
+                            None => line,
+                        });
+                    }
+                }
+            }
+        }
+
+        result.unwrap_or_else(Error)
     }
 
-    pub fn set_inputs(&mut self, inputs: &[i64]) {
-        self.inputs = inputs.to_owned();
-        self.inputs.reverse();
-        self.status = Running;
+    fn decode(&mut self, instruction: i64) -> Decoded {
+        let index = self.index;
+        match self.decoded.as_mut() {
+            Some(decoded) => {
+                if index >= decoded.len() {
+                    decoded.resize(index + 1, None);
+                }
+                *decoded[index].get_or_insert_with(|| decode_lenient(instruction))
+            }
+            None => decode_lenient(instruction),
+        }
     }
 
-    pub fn add_input(mut self, input: i64) -> Self {
-        self.add_input_borrowing(input);
-        self
+    // Every write goes through here so a cached decoding never outlives the cell it was made from
+    fn write(&mut self, address: usize, value: i64) {
+        self.memory.set(address, value);
+        if let Some(entry) = self
+            .decoded
+            .as_mut()
+            .and_then(|decoded| decoded.get_mut(address))
+        {
+            *entry = None;
+        }
     }
 
-    pub fn add_input_borrowing(&mut self, input: i64) {
-        self.inputs.reverse();
-        self.inputs.push(input);
-        self.inputs.reverse();
+    // Addresses are stored as i64 in memory, casting a negative one would wrap into a huge resize
+    fn address(&self, target: i64) -> Result<usize, IntcodeError> {
+        if target < 0 {
+            Err(IntcodeError::NegativeAddress {
+                address: self.index,
+                target,
+            })
+        } else {
+            Ok(target as usize)
+        }
+    }
 
-        if self.status == Waiting {
-            self.status = Running;
+    fn immediate_write(&mut self) -> IntcodeError {
+        IntcodeError::ImmediateWrite {
+            address: self.index,
+            instruction: self.memory.get(self.index),
         }
     }
 
@@ -154,16 +649,11 @@ impl Intcode {
         &mut self,
         flags: ParameterFlags,
         op_len: usize,
-        op_name: String,
-    ) -> (Option<i64>, Option<i64>, Option<usize>) {
-        if op_len == 3 && flags.2 == Immediate {
-            panic!(
-                "{}: Something went terribly wrong: param 3 is in immediate mode",
-                op_name
-            );
+    ) -> Result<Operands, IntcodeError> {
+        if op_len == 3 && flags[2] == Immediate {
+            return Err(self.immediate_write());
         }
 
-        let first_value;
         let mut second_value = None;
         let mut store_index = None;
 
@@ -174,182 +664,390 @@ impl Intcode {
             0
         };
 
-        first_value = match flags.0 {
-            Position => Some(self.memory.get(first_index as usize)),
+        let first_value = match flags[0] {
+            Position => Some(self.memory.get(self.address(first_index)?)),
             Immediate => Some(first_index),
             Relative => Some(
                 self.memory
-                    .get((first_index + self.relative_offset) as usize),
+                    .get(self.address(first_index + self.relative_offset)?),
             ),
         };
 
         if op_len >= 2 {
-            second_value = match flags.1 {
-                Position => Some(self.memory.get(second_index as usize)),
+            second_value = match flags[1] {
+                Position => Some(self.memory.get(self.address(second_index)?)),
                 Immediate => Some(second_index),
                 Relative => Some(
                     self.memory
-                        .get((second_index + self.relative_offset) as usize),
+                        .get(self.address(second_index + self.relative_offset)?),
                 ),
             };
         }
 
         if op_len == 3 {
-            store_index = match flags.2 {
-                Position => Some(self.memory.get(self.index + 3) as usize),
-                Relative => Some((self.memory.get(self.index + 3) + self.relative_offset) as usize),
+            let raw_index = self.memory.get(self.index + 3);
+            store_index = match flags[2] {
+                Position => Some(self.address(raw_index)?),
+                Relative => Some(self.address(raw_index + self.relative_offset)?),
                 Immediate => {
                     unreachable!("Immediate in position 3 should NOT happen at this point")
                 }
             };
         }
 
-        (first_value, second_value, store_index)
+        Ok((first_value, second_value, store_index))
     }
 
-    fn add(&mut self, flags: ParameterFlags) -> CompStatus {
-        let (first_value, second_value, store_index) = self.prepare_op(flags, 3, "Add".to_string());
+    fn add(&mut self, flags: ParameterFlags) -> Result<CompStatus, IntcodeError> {
+        let (first_value, second_value, store_index) = self.prepare_op(flags, 3)?;
 
-        self.memory.set(
+        self.write(
             store_index.unwrap(),
             first_value.unwrap() + second_value.unwrap(),
         );
         self.index += 4;
-        Running
+        Ok(Running)
     }
 
-    fn mul(&mut self, flags: ParameterFlags) -> CompStatus {
-        let (first_value, second_value, store_index) = self.prepare_op(flags, 3, "Mul".to_string());
+    fn mul(&mut self, flags: ParameterFlags) -> Result<CompStatus, IntcodeError> {
+        let (first_value, second_value, store_index) = self.prepare_op(flags, 3)?;
 
-        self.memory.set(
+        self.write(
             store_index.unwrap(),
             first_value.unwrap() * second_value.unwrap(),
         );
         self.index += 4;
-        Running
+        Ok(Running)
     }
 
-    fn use_input(&mut self, flags: ParameterFlags) -> CompStatus {
-        if self.inputs.is_empty() {
-            return Waiting;
-        }
-
-        let new_input = self.inputs.pop().unwrap();
-
-        if flags.0 == Immediate {
-            panic!(
-                "Input: Something went terribly wrong: param 1 is in immediate mode: {}",
-                self.memory[self.index]
-            );
+    fn use_input(&mut self, flags: ParameterFlags) -> Result<CompStatus, IntcodeError> {
+        if flags[0] == Immediate {
+            return Err(self.immediate_write());
         }
 
-        let index = if flags.0 == Position {
-            self.memory.get(self.index + 1) as usize
+        let raw_index = self.memory.get(self.index + 1);
+        let index = if flags[0] == Position {
+            self.address(raw_index)?
         } else {
-            (self.memory.get(self.index + 1) + self.relative_offset) as usize
+            self.address(raw_index + self.relative_offset)?
         };
 
-        self.memory.set(index, new_input);
+        let new_input = match self.inputs.next_input() {
+            Some(value) => value,
+            None => return Ok(Waiting),
+        };
+        self.write(index, new_input);
 
         self.index += 2;
-        Running
+        Ok(Running)
     }
 
-    fn output(&mut self, flags: ParameterFlags) -> CompStatus {
-        let index = self.memory[self.index + 1];
+    fn output(&mut self, flags: ParameterFlags) -> Result<CompStatus, IntcodeError> {
+        let index = self.memory.get(self.index + 1);
 
-        self.output = match flags.0 {
-            Position => self.memory[index as usize],
-            Immediate => index,
-            Relative => self.memory[(index + self.relative_offset) as usize],
+        let target = match flags[0] {
+            Position => self.address(index)?,
+            Immediate => self.index + 1,
+            Relative => self.address(index + self.relative_offset)?,
         };
 
-        // println!("{}", self.output);
+        self.output = match self.memory.as_slice().get(target) {
+            Some(value) => *value,
+            None => {
+                return Err(IntcodeError::OutputOutOfBounds {
+                    address: self.index,
+                    target,
+                })
+            }
+        };
 
-        self.outputs.reverse();
-        self.outputs.push(self.output);
-        self.outputs.reverse();
+        self.outputs.push_output(self.output);
 
         self.index += 2;
-        Running
-    }
-
-    pub fn get_outputs(&mut self) -> Vec<i64> {
-        let outs = self.outputs.clone();
-        self.outputs = vec![];
-        outs
-    }
-
-    pub fn peek_outputs(&mut self) -> &Vec<i64> {
-        &self.outputs
+        Ok(Running)
     }
 
-    fn jump_if_true(&mut self, flags: ParameterFlags) -> CompStatus {
-        let (first_value, second_value, _) = self.prepare_op(flags, 2, "Jump if true".to_string());
+    fn jump_if_true(&mut self, flags: ParameterFlags) -> Result<CompStatus, IntcodeError> {
+        let (first_value, second_value, _) = self.prepare_op(flags, 2)?;
         let first_value = first_value.unwrap();
         let second_value = second_value.unwrap();
 
         self.index = if first_value != 0 {
-            second_value as usize
+            self.address(second_value)?
         } else {
             self.index + 3
         };
-        Running
+        Ok(Running)
     }
 
-    fn jump_if_false(&mut self, flags: ParameterFlags) -> CompStatus {
-        let (first_value, second_value, _) = self.prepare_op(flags, 2, "Jump if false".to_string());
+    fn jump_if_false(&mut self, flags: ParameterFlags) -> Result<CompStatus, IntcodeError> {
+        let (first_value, second_value, _) = self.prepare_op(flags, 2)?;
         let first_value = first_value.unwrap();
         let second_value = second_value.unwrap();
 
         self.index = if first_value == 0 {
-            second_value as usize
+            self.address(second_value)?
         } else {
             self.index + 3
         };
-        Running
+        Ok(Running)
     }
 
-    fn less_than(&mut self, flags: ParameterFlags) -> CompStatus {
-        let (first_value, second_value, store_index) =
-            self.prepare_op(flags, 3, "Less than".to_string());
+    fn less_than(&mut self, flags: ParameterFlags) -> Result<CompStatus, IntcodeError> {
+        let (first_value, second_value, store_index) = self.prepare_op(flags, 3)?;
         let first_value = first_value.unwrap();
         let second_value = second_value.unwrap();
         let store_index = store_index.unwrap();
 
-        self.memory
-            .set(store_index, if first_value < second_value { 1 } else { 0 });
+        self.write(store_index, if first_value < second_value { 1 } else { 0 });
 
         self.index += 4;
-        Running
+        Ok(Running)
     }
 
-    fn equals(&mut self, flags: ParameterFlags) -> CompStatus {
-        let (first_value, second_value, store_index) =
-            self.prepare_op(flags, 3, "equals".to_string());
+    fn equals(&mut self, flags: ParameterFlags) -> Result<CompStatus, IntcodeError> {
+        let (first_value, second_value, store_index) = self.prepare_op(flags, 3)?;
         let first_value = first_value.unwrap();
         let second_value = second_value.unwrap();
         let store_index = store_index.unwrap();
 
-        self.memory
-            .set(store_index, if first_value == second_value { 1 } else { 0 });
+        self.write(store_index, if first_value == second_value { 1 } else { 0 });
 
         self.index += 4;
-        Running
+        Ok(Running)
     }
 
-    fn set_relative_offset(&mut self, flags: ParameterFlags) -> CompStatus {
-        let (first_value, _, _) = self.prepare_op(flags, 1, "Set relative offset".to_string());
+    fn set_relative_offset(&mut self, flags: ParameterFlags) -> Result<CompStatus, IntcodeError> {
+        let (first_value, _, _) = self.prepare_op(flags, 1)?;
         self.relative_offset += first_value.unwrap();
         self.index += 2;
-        Running
+        Ok(Running)
     }
 }
 
-pub fn prepare_memory(input: String) -> Vec<i64> {
-    input
-        .trim()
-        .split(',')
-        .map(|x| (x.trim().parse::<i64>().unwrap_or(0)))
-        .collect::<Vec<_>>()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn fault(memory: Vec<i64>) -> IntcodeError {
+        match Intcode::new(memory).run().status {
+            Error(error) => error,
+            status => panic!("the machine stopped with {:?}", status),
+        }
+    }
+
+    #[test]
+    fn faults_carry_the_faulty_address() {
+        assert_eq!(
+            fault(vec![1101, 1, 1, 5, 42, 0]),
+            IntcodeError::UnknownOpcode {
+                address: 4,
+                opcode: 42
+            }
+        );
+        assert_eq!(
+            fault(vec![11101, 1, 1, 0, 99]),
+            IntcodeError::ImmediateWrite {
+                address: 0,
+                instruction: 11101
+            }
+        );
+        assert_eq!(
+            fault(vec![1, -3, 0, 0, 99]),
+            IntcodeError::NegativeAddress {
+                address: 0,
+                target: -3
+            }
+        );
+        assert_eq!(
+            fault(vec![4, 10, 99]),
+            IntcodeError::OutputOutOfBounds {
+                address: 0,
+                target: 10
+            }
+        );
+    }
+
+    #[test]
+    fn faults_are_described() {
+        assert_eq!(
+            fault(vec![42]).to_string(),
+            "unknown opcode 42 at address 0"
+        );
+        assert_eq!(
+            fault(vec![104, 1, 4, -1]).to_string(),
+            "instruction at address 2 uses negative address -1"
+        );
+    }
+
+    #[test]
+    fn a_fault_stops_the_machine() {
+        let mut machine = Intcode::new(vec![104, 7, 42, 104, 8, 99]).run();
+
+        assert!(matches!(machine.status, Error(_)));
+        assert_eq!(machine.get_outputs(), vec![7]);
+        assert_eq!(machine.index, 2);
+    }
+
+    // Echoes every input until there are none left
+    const ECHO: [i64; 7] = [3, 7, 4, 7, 1105, 1, 0];
+
+    #[test]
+    fn closures_as_source_and_sink() {
+        let mut next = 0;
+        let mut seen = vec![];
+        let machine = Intcode::with_io(
+            ECHO.to_vec(),
+            FnInput(|| {
+                next += 1;
+                Some(next).filter(|value| *value <= 3)
+            }),
+            FnOutput(|value| seen.push(value)),
+        )
+        .run();
+
+        assert_eq!(machine.status, Waiting);
+        drop(machine);
+        assert_eq!(seen, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn ascii_input_feeds_one_line_at_a_time() {
+        let mut machine = Intcode::with_io(
+            ECHO.to_vec(),
+            AsciiInput::new("hi\r\nyo\n".as_bytes()),
+            VecDeque::new(),
+        )
+        .run();
+
+        assert_eq!(machine.status, Waiting);
+        assert_eq!(machine.get_outputs(), vec![104, 105, 10, 121, 111, 10]);
+    }
+
+    #[test]
+    fn channels_as_source_and_sink() {
+        let (input, receiver) = mpsc::channel();
+        let (sender, output) = mpsc::channel();
+        input.send(4).unwrap();
+        input.send(2).unwrap();
+        // Once the inputs are sent, a closed channel makes the machine wait
+        drop(input);
+
+        let machine = Intcode::with_io(ECHO.to_vec(), receiver, sender).run();
+        assert_eq!(machine.status, Waiting);
+        drop(machine);
+        assert_eq!(output.iter().collect::<Vec<_>>(), vec![4, 2]);
+    }
+
+    #[test]
+    fn both_decoders_agree_on_valid_instructions() {
+        for opcode in Opcode::ALL.iter() {
+            for modes in 0..27 {
+                let digits = [modes % 3, modes / 3 % 3, modes / 9];
+                let instruction =
+                    opcode.code() + 100 * digits[0] + 1000 * digits[1] + 10000 * digits[2];
+                let (strict_opcode, strict_modes) = decode(instruction).unwrap();
+
+                assert_eq!(
+                    decode_lenient(instruction),
+                    (Some(strict_opcode), strict_modes)
+                );
+                assert_eq!(strict_opcode, *opcode);
+            }
+        }
+
+        // Only the machine accepts any mode digit above 1 as relative
+        assert_eq!(decode(304), None);
+        assert_eq!(
+            decode_lenient(304),
+            (Some(Opcode::Output), [Relative, Position, Position])
+        );
+        assert_eq!(decode_lenient(-1).0, None);
+    }
+
+    fn cached_and_plain(memory: Vec<i64>, inputs: &[i64]) -> [(Vec<i64>, CompStatus); 2] {
+        let run = |cached: bool| {
+            let mut machine = Intcode::new(memory.clone());
+            machine.set_inputs(inputs);
+            if cached {
+                machine.enable_decode_cache();
+            }
+            let mut machine = machine.run();
+            (machine.get_outputs(), machine.status)
+        };
+        [run(true), run(false)]
+    }
+
+    #[test]
+    fn the_decode_cache_changes_nothing() {
+        // Day 5's comparison with 8, jumping around in every mode
+        let compare = vec![
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0,
+            0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4,
+            20, 1105, 1, 46, 98, 99,
+        ];
+        for input in 7..10 {
+            let [cached, plain] = cached_and_plain(compare.clone(), &[input]);
+            assert_eq!(cached, plain);
+        }
+
+        let [cached, plain] = cached_and_plain(vec![42], &[]);
+        assert_eq!(cached, plain);
+    }
+
+    #[test]
+    fn writes_invalidate_the_decode_cache() {
+        // Turns the halt at 4 into an output of 42 before reaching it
+        let [cached, plain] = cached_and_plain(vec![1101, 104, 0, 4, 99, 42, 99], &[]);
+
+        assert_eq!(cached, (vec![42], Halted));
+        assert_eq!(cached, plain);
+    }
+
+    // Counts up in [7] forever
+    fn forever() -> Intcode {
+        Intcode::new(vec![1001, 7, 1, 7, 1105, 1, 0, 0])
+    }
+
+    #[test]
+    fn budgets_stop_runaway_programs() {
+        let mut machine = forever();
+        machine.set_budget(Some(10));
+        let machine = machine.run();
+
+        assert_eq!(machine.status, OutOfBudget);
+        assert_eq!(machine.steps(), 10);
+        assert_eq!(machine.memory()[7], 5);
+
+        // Stays stopped until the budget is raised
+        let mut machine = machine.run();
+        assert_eq!(machine.steps(), 10);
+        machine.set_budget(Some(14));
+        assert_eq!(machine.status, Running);
+        let machine = machine.run();
+        assert_eq!((machine.status, machine.steps()), (OutOfBudget, 14));
+    }
+
+    #[test]
+    fn run_for_pauses_without_a_budget() {
+        let machine = forever().run_for(7);
+        assert_eq!((machine.status, machine.steps()), (OutOfBudget, 7));
+
+        let machine = machine.run_for(3);
+        assert_eq!((machine.status, machine.steps()), (OutOfBudget, 10));
+
+        let machine = Intcode::new(vec![104, 1, 99]).run_for(100);
+        assert_eq!((machine.status, machine.steps()), (Halted, 2));
+    }
+
+    #[test]
+    fn waiting_and_faults_take_no_step() {
+        let machine = Intcode::new(vec![3, 5, 4, 5, 42, 0]).run();
+        assert_eq!((machine.status, machine.steps()), (Waiting, 0));
+
+        let machine = machine.add_input(9).run();
+        assert!(matches!(machine.status, Error(_)));
+        assert_eq!(machine.steps(), 2);
+    }
 }
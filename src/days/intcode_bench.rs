@@ -0,0 +1,151 @@
+use std::time::{Duration, Instant};
+
+use super::assembler;
+use super::bench::format_duration;
+use super::disassembler;
+use super::intcode::{decode_lenient, Intcode, Opcode, ParameterMode};
+
+// Sums i * i for i in 0..n, n being the only input: a tight loop of arithmetic, compares and jumps
+const SQUARES: &str = "
+    in [n]
+loop:
+    mul [i], [i], [square]
+    add [sum], [square], [sum]
+    add [i], #1, [i]
+    lt [i], [n], [more]
+    jnz [more], #loop
+    out [sum]
+    hlt
+n: db 0
+i: db 0
+square: db 0
+sum: db 0
+more: db 0
+";
+
+// Small stack based program started from scratch many times, like the day19 drone probes
+const PROBE: &str = "
+    arb #stack
+    in [rb+0]
+    in [rb+1]
+count:
+    add [rb+0], [rb+1], [rb+2]
+    add [rb+1], #-1, [rb+1]
+    jnz [rb+1], #count
+    out [rb+2]
+    hlt
+stack: db 0, 0, 0
+";
+
+fn time<F: FnMut()>(mut job: F) -> Duration {
+    let start = Instant::now();
+    job();
+    start.elapsed()
+}
+
+// The decoder Intcode used before, kept only as a reference point for the decode timings
+fn legacy_decode(instruction: i64) -> (String, [ParameterMode; 3]) {
+    fn convert_to_flag(input: char) -> ParameterMode {
+        if input == '0' {
+            ParameterMode::Position
+        } else if input == '1' {
+            ParameterMode::Immediate
+        } else {
+            ParameterMode::Relative
+        }
+    }
+
+    let instruction = format!("{:05}", instruction);
+    let mut instruction = instruction.chars();
+    let flags = [
+        convert_to_flag(instruction.next().unwrap()),
+        convert_to_flag(instruction.next().unwrap()),
+        convert_to_flag(instruction.next().unwrap()),
+    ];
+    (instruction.collect(), [flags[2], flags[1], flags[0]])
+}
+
+fn run_squares(program: &[i64], cached: bool) -> i64 {
+    let mut machine = Intcode::new(program.to_vec()).add_input(1_000_000);
+    if cached {
+        machine.enable_decode_cache();
+    }
+    machine.run().get_outputs()[0]
+}
+
+fn run_probes(program: &[i64], cached: bool) -> i64 {
+    let mut original = Intcode::new(program.to_vec());
+    if cached {
+        original.enable_decode_cache();
+    }
+    (0..20_000)
+        .map(|x| {
+            original
+                .clone()
+                .add_input(x)
+                .add_input(50)
+                .run()
+                .get_outputs()[0]
+        })
+        .sum()
+}
+
+fn report(name: &str, duration: Duration, baseline: Duration, result: i64) {
+    println!(
+        "  {:<24}{:>10} {:>6.2}x  -> {}",
+        name,
+        format_duration(duration),
+        baseline.as_secs_f64() / duration.as_secs_f64(),
+        result
+    );
+}
+
+// Meant for release builds: cargo run --release -- bench-intcode
+pub fn run() {
+    let squares = assembler::assemble(SQUARES).unwrap();
+    let probe = assembler::assemble(PROBE).unwrap();
+
+    // Only the cells that really hold instructions, so both decoders must agree on the checksum.
+    // The integer decoder timed is the lenient one the machine runs.
+    let mut instructions = vec![];
+    let mut address = 0;
+    while let Some(instruction) = disassembler::decode_at(&squares, address) {
+        instructions.push(squares[address]);
+        if instruction.opcode == Opcode::Halt {
+            break;
+        }
+        address = instruction.next_address();
+    }
+
+    println!("Decoding 5M instructions:");
+    let mut legacy_checksum = 0;
+    let legacy = time(|| {
+        for instruction in instructions.iter().cycle().take(5_000_000) {
+            let (opcode, modes) = legacy_decode(*instruction);
+            legacy_checksum +=
+                opcode.parse::<i64>().unwrap() + modes.iter().map(|mode| mode.digit()).sum::<i64>();
+        }
+    });
+    report("string (legacy)", legacy, legacy, legacy_checksum);
+    let mut checksum = 0;
+    let arithmetic = time(|| {
+        for instruction in instructions.iter().cycle().take(5_000_000) {
+            let (opcode, modes) = decode_lenient(*instruction);
+            checksum += opcode.unwrap().code() + modes.iter().map(|mode| mode.digit()).sum::<i64>();
+        }
+    });
+    report("integer", arithmetic, legacy, checksum);
+
+    let mut result = 0;
+    println!("Squares loop, 5M instructions:");
+    let plain = time(|| result = run_squares(&squares, false));
+    report("integer decoder", plain, plain, result);
+    let cached = time(|| result = run_squares(&squares, true));
+    report("decode cache", cached, plain, result);
+
+    println!("20k fresh probes, 150 instructions each:");
+    let plain = time(|| result = run_probes(&probe, false));
+    report("integer decoder", plain, plain, result);
+    let cached = time(|| result = run_probes(&probe, true));
+    report("decode cache", cached, plain, result);
+}
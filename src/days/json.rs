@@ -0,0 +1,84 @@
+use std::error::Error;
+use std::time::Duration;
+
+use super::answer::Answer;
+
+// Only what a run record needs, a dependency would be overkill
+fn string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// One object per star, on a single line so the output can be read line by line (JSON Lines).
+// `status` is "solved", "unsolved" or "error", `answer` is a number for numeric answers,
+// a string otherwise (images keep their newlines), and null when there is none.
+pub fn record(
+    day: u32,
+    part: u32,
+    result: &Result<Answer, Box<dyn Error + 'static>>,
+    time: Duration,
+) -> String {
+    let (status, answer, error) = match result {
+        Ok(Answer::Number(number)) => ("solved", number.to_string(), "null".to_string()),
+        Ok(Answer::Text(text)) | Ok(Answer::Image(text)) => {
+            ("solved", string(text), "null".to_string())
+        }
+        Ok(Answer::Unsolved) => ("unsolved", "null".to_string(), "null".to_string()),
+        Err(error) => ("error", "null".to_string(), string(&error.to_string())),
+    };
+
+    format!(
+        "{{\"day\":{},\"part\":{},\"status\":{},\"answer\":{},\"time_ms\":{:.3},\"error\":{}}}",
+        day,
+        part,
+        string(status),
+        answer,
+        time.as_secs_f64() * 1_000.0,
+        error
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_record_per_status() {
+        let time = Duration::from_micros(1500);
+
+        assert_eq!(
+            record(1, 1, &Ok(Answer::Number(-3)), time),
+            r#"{"day":1,"part":1,"status":"solved","answer":-3,"time_ms":1.500,"error":null}"#
+        );
+        assert_eq!(
+            record(8, 2, &Ok(Answer::Image("#.\n.#".to_string())), time),
+            r##"{"day":8,"part":2,"status":"solved","answer":"#.\n.#","time_ms":1.500,"error":null}"##
+        );
+        assert_eq!(
+            record(25, 2, &Ok(Answer::Unsolved), time),
+            r#"{"day":25,"part":2,"status":"unsolved","answer":null,"time_ms":1.500,"error":null}"#
+        );
+        assert_eq!(
+            record(3, 1, &Err("Unknown direction \"X\"".into()), time),
+            r#"{"day":3,"part":1,"status":"error","answer":null,"time_ms":1.500,"error":"Unknown direction \"X\""}"#
+        );
+    }
+
+    #[test]
+    fn strings_are_escaped() {
+        assert_eq!(string("a\\b\tc\r\u{1}é"), r#""a\\b\tc\r\u0001é""#);
+    }
+}
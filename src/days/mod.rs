@@ -1,4 +1,26 @@
+use std::error::Error;
+
+use self::answer::Answer;
+
+pub mod answer;
+pub mod ascii;
+pub mod assembler;
+pub mod bench;
+pub mod debugger;
+pub mod disassembler;
+pub mod grid;
+pub mod input;
 pub mod intcode;
+pub mod intcode_bench;
+pub mod json;
+pub mod network;
+pub mod parallel;
+pub mod pipeline;
+pub mod profiler;
+pub mod program;
+pub mod snapshot;
+pub mod threaded;
+pub mod verify;
 
 pub mod day1;
 pub mod day10;
@@ -25,3 +47,210 @@ pub mod day7;
 pub mod day8;
 pub mod day9;
 pub mod finalday;
+
+pub type Star = fn() -> Result<Answer, Box<dyn Error + 'static>>;
+
+pub struct Day {
+    pub number: u32,
+    pub title: &'static str,
+    // Names given to input::read_input, the files the stars read
+    pub inputs: &'static [&'static str],
+    pub first_star: Star,
+    // A day is registered as soon as its first star is solved
+    pub second_star: Option<Star>,
+}
+
+// Every solved day, in order. A new day only needs its module above and an entry here.
+pub const DAYS: &[Day] = &[
+    Day {
+        number: 1,
+        title: "The Tyranny of the Rocket Equation",
+        inputs: &["day1"],
+        first_star: day1::first_star,
+        second_star: Some(day1::second_star),
+    },
+    Day {
+        number: 2,
+        title: "1202 Program Alarm",
+        inputs: &["day2"],
+        first_star: day2::first_star,
+        second_star: Some(day2::second_star),
+    },
+    Day {
+        number: 3,
+        title: "Crossed Wires",
+        inputs: &["day3"],
+        first_star: day3::first_star,
+        second_star: Some(day3::second_star),
+    },
+    Day {
+        number: 4,
+        title: "Secure Container",
+        inputs: &[],
+        first_star: day4::first_star,
+        second_star: Some(day4::second_star),
+    },
+    Day {
+        number: 5,
+        title: "Sunny with a Chance of Asteroids",
+        inputs: &["day5"],
+        first_star: day5::first_star,
+        second_star: Some(day5::second_star),
+    },
+    Day {
+        number: 6,
+        title: "Universal Orbit Map",
+        inputs: &["day6"],
+        first_star: day6::first_star,
+        second_star: Some(day6::second_star),
+    },
+    Day {
+        number: 7,
+        title: "Amplification Circuit",
+        inputs: &["day7"],
+        first_star: day7::first_star,
+        second_star: Some(day7::second_star),
+    },
+    Day {
+        number: 8,
+        title: "Space Image Format",
+        inputs: &["day8"],
+        first_star: day8::first_star,
+        second_star: Some(day8::second_star),
+    },
+    Day {
+        number: 9,
+        title: "Sensor Boost",
+        inputs: &["day9"],
+        first_star: day9::first_star,
+        second_star: Some(day9::second_star),
+    },
+    Day {
+        number: 10,
+        title: "Monitoring Station",
+        inputs: &["day10"],
+        first_star: day10::first_star,
+        second_star: Some(day10::second_star),
+    },
+    Day {
+        number: 11,
+        title: "Space Police",
+        inputs: &["day11"],
+        first_star: day11::first_star,
+        second_star: Some(day11::second_star),
+    },
+    Day {
+        number: 12,
+        title: "The N-Body Problem",
+        inputs: &["day12"],
+        first_star: day12::first_star,
+        second_star: Some(day12::second_star),
+    },
+    Day {
+        number: 13,
+        title: "Care Package",
+        inputs: &["day13"],
+        first_star: day13::first_star,
+        second_star: Some(day13::second_star),
+    },
+    Day {
+        number: 14,
+        title: "Space Stoichiometry",
+        inputs: &["day14"],
+        first_star: day14::first_star,
+        second_star: Some(day14::second_star),
+    },
+    Day {
+        number: 15,
+        title: "Oxygen System",
+        inputs: &["day15"],
+        first_star: day15::first_star,
+        second_star: Some(day15::second_star),
+    },
+    Day {
+        number: 16,
+        title: "Flawed Frequency Transmission",
+        inputs: &["day16"],
+        first_star: day16::first_star,
+        second_star: Some(day16::second_star),
+    },
+    Day {
+        number: 17,
+        title: "Set and Forget",
+        inputs: &["day17"],
+        first_star: day17::first_star,
+        second_star: Some(day17::second_star),
+    },
+    Day {
+        number: 18,
+        title: "Many-Worlds Interpretation",
+        inputs: &["day18_a", "day18_b"],
+        first_star: day18::first_star,
+        second_star: Some(day18::second_star),
+    },
+    Day {
+        number: 19,
+        title: "Tractor Beam",
+        inputs: &["day19"],
+        first_star: day19::first_star,
+        second_star: Some(day19::second_star),
+    },
+    Day {
+        number: 20,
+        title: "Donut Maze",
+        inputs: &["day20"],
+        first_star: day20::first_star,
+        second_star: Some(day20::second_star),
+    },
+    Day {
+        number: 21,
+        title: "Springdroid Adventure",
+        inputs: &["day21"],
+        first_star: day21::first_star,
+        second_star: Some(day21::second_star),
+    },
+    Day {
+        number: 22,
+        title: "Slam Shuffle",
+        inputs: &["day22"],
+        first_star: day22::first_star,
+        second_star: Some(day22::second_star),
+    },
+    Day {
+        number: 23,
+        title: "Category Six",
+        inputs: &["day23"],
+        first_star: day23::first_star,
+        second_star: Some(day23::second_star),
+    },
+    Day {
+        number: 24,
+        title: "Planet of Discord",
+        inputs: &["day24"],
+        first_star: day24::first_star,
+        second_star: Some(day24::second_star),
+    },
+    Day {
+        number: 25,
+        title: "Cryostasis",
+        inputs: &["finalday"],
+        first_star: finalday::first_star,
+        // There is no second puzzle, the last star is given for collecting the 49 others
+        second_star: None,
+    },
+];
+
+impl Day {
+    pub fn solve(&self, part: u32) -> Result<Answer, Box<dyn Error + 'static>> {
+        match (part, self.second_star) {
+            (1, _) => (self.first_star)(),
+            (2, Some(second_star)) => second_star(),
+            (2, None) => Ok(Answer::Unsolved),
+            _ => Err(format!("Day {} has no part {}", self.number, part).into()),
+        }
+    }
+}
+
+pub fn find(number: u32) -> Option<&'static Day> {
+    DAYS.iter().find(|day| day.number == number)
+}
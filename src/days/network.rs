@@ -0,0 +1,336 @@
+use std::collections::VecDeque;
+use std::thread::JoinHandle;
+
+use super::intcode::{CompStatus, Intcode};
+use super::threaded::{self, Inbox, Monitor, Port, Received, Tagged};
+
+// Read by a node when no packet is waiting, like day23's NICs expect
+const EMPTY_INPUT: i64 = -1;
+
+pub type Node = Intcode<Inbox<i64>, Tagged>;
+
+// Nodes write packets as three outputs: destination, x, y. For a NAT delivery the source is
+// whatever the NAT put there, the day23 one keeps the node that sent the packet to it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Packet {
+    pub source: usize,
+    pub destination: i64,
+    pub x: i64,
+    pub y: i64,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Route {
+    Node(usize),
+    Nat,
+    Drop,
+}
+
+pub trait Router {
+    fn route(&mut self, packet: &Packet) -> Route;
+}
+
+// Node n listens on address n, one more address may reach the NAT, anything else is dropped
+#[derive(Copy, Clone, Debug)]
+pub struct AddressRouter {
+    pub nodes: usize,
+    pub nat: Option<i64>,
+}
+
+impl Router for AddressRouter {
+    fn route(&mut self, packet: &Packet) -> Route {
+        if Some(packet.destination) == self.nat {
+            Route::Nat
+        } else if packet.destination >= 0 && (packet.destination as usize) < self.nodes {
+            Route::Node(packet.destination as usize)
+        } else {
+            Route::Drop
+        }
+    }
+}
+
+pub trait Nat {
+    fn receive(&mut self, packet: Packet);
+    // Called every time the whole network is idle, a packet returned here is sent to its destination node
+    fn wake(&mut self) -> Option<Packet>;
+}
+
+// Day23's NAT: remembers the last packet it got and resends it to node 0 when the network is idle
+#[derive(Clone, Debug, Default)]
+pub struct LastPacketNat {
+    pub last: Option<Packet>,
+}
+
+impl Nat for LastPacketNat {
+    fn receive(&mut self, packet: Packet) {
+        self.last = Some(packet);
+    }
+
+    fn wake(&mut self) -> Option<Packet> {
+        self.last.map(|packet| Packet {
+            destination: 0,
+            ..packet
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Event {
+    PacketSent { packet: Packet, route: Route },
+    // Polled its empty queue twice in a row without sending anything
+    NodeIdle(usize),
+    NetworkIdle,
+    NatDelivery(Packet),
+    // Halted, failed or ran out of budget, the node is out of the network for good
+    NodeStopped { node: usize, status: CompStatus },
+}
+
+// Every node runs on its own thread, iterating over the network routes the packets and yields
+// what happened. Nodes run concurrently so events of different nodes may interleave differently
+// from one run to the other, but NetworkIdle is only seen once every node is idle and nothing is in flight.
+// The iteration ends when the network is idle and the NAT has nothing to send, or every node stopped.
+#[derive(Debug)]
+pub struct Network<R, N> {
+    router: R,
+    nat: N,
+    ports: Vec<Port<i64>>,
+    inbox_ids: Vec<usize>,
+    nodes: Vec<Option<JoinHandle<Node>>>,
+    outputs: Inbox<(usize, i64)>,
+    // Values of the packet each node is writing
+    pending: Vec<Vec<i64>>,
+    events: VecDeque<Event>,
+    done: bool,
+}
+
+impl<R: Router, N: Nat> Network<R, N> {
+    // Node n runs images[n] and gets n as its first input, budget caps the instructions of each node
+    pub fn boot(images: Vec<Vec<i64>>, router: R, nat: N, budget: Option<u64>) -> Self {
+        let monitor = Monitor::new();
+        monitor.report_idle();
+        let (output_port, outputs) = monitor.channel();
+
+        let mut ports = vec![];
+        let mut inbox_ids = vec![];
+        let mut nodes = vec![];
+        for (address, image) in images.into_iter().enumerate() {
+            let (port, inbox) = monitor.channel();
+            port.send(address as i64);
+
+            let output = output_port.clone().owned_by(&inbox).tagged(address);
+            inbox_ids.push(inbox.id());
+            let mut node = Intcode::with_io(image, inbox.with_default(EMPTY_INPUT), output);
+            node.enable_decode_cache();
+            node.set_budget(budget);
+
+            ports.push(port);
+            nodes.push(Some(threaded::spawn(node)));
+        }
+
+        Network {
+            router,
+            nat,
+            pending: vec![vec![]; ports.len()],
+            ports,
+            inbox_ids,
+            nodes,
+            outputs,
+            events: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    fn deliver(&self, node: usize, packet: &Packet) {
+        if let Some(port) = self.ports.get(node) {
+            port.send_all(vec![packet.x, packet.y]);
+        }
+    }
+
+    fn output(&mut self, source: usize, value: i64) {
+        let pending = &mut self.pending[source];
+        pending.push(value);
+        if pending.len() < 3 {
+            return;
+        }
+
+        let packet = Packet {
+            source,
+            destination: pending[0],
+            x: pending[1],
+            y: pending[2],
+        };
+        pending.clear();
+
+        let route = self.router.route(&packet);
+        match route {
+            Route::Node(node) => self.deliver(node, &packet),
+            Route::Nat => self.nat.receive(packet),
+            Route::Drop => {}
+        }
+        self.events.push_back(Event::PacketSent { packet, route });
+    }
+
+    fn idle(&mut self, inbox: usize, retired: bool) {
+        let node = match self.inbox_ids.iter().position(|id| *id == inbox) {
+            Some(node) => node,
+            None => return,
+        };
+
+        if !retired {
+            self.events.push_back(Event::NodeIdle(node));
+        } else if let Some(handle) = self.nodes[node].take() {
+            // The thread retires the inbox right before it returns, joining doesn't wait long
+            let status = match handle.join() {
+                Ok(machine) => machine.status,
+                Err(panic) => std::panic::resume_unwind(panic),
+            };
+            self.events.push_back(Event::NodeStopped { node, status });
+        }
+    }
+
+    fn network_idle(&mut self) {
+        self.events.push_back(Event::NetworkIdle);
+        match self.nat.wake() {
+            Some(packet) => {
+                if packet.destination >= 0 {
+                    self.deliver(packet.destination as usize, &packet);
+                }
+                self.events.push_back(Event::NatDelivery(packet));
+            }
+            None => self.done = true,
+        }
+    }
+}
+
+impl<R: Router, N: Nat> Iterator for Network<R, N> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        while self.events.is_empty() && !self.done {
+            match self.outputs.recv_or_quiescent() {
+                Received::Value((source, value)) => self.output(source, value),
+                Received::Idle { inbox, retired } => self.idle(inbox, retired),
+                Received::Quiescent => self.network_idle(),
+                Received::Closed => self.done = true,
+            }
+        }
+        self.events.pop_front()
+    }
+}
+
+// Closing the inboxes makes the nodes still running stop, their threads are joined here
+impl<R, N> Drop for Network<R, N> {
+    fn drop(&mut self) {
+        self.ports.clear();
+        for handle in self.nodes.iter_mut().filter_map(Option::take) {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days::assembler::assemble;
+
+    // Node 0 sends (10, 20) to node 1, then every node sends what it gets to 255 with y + 1
+    const NODE: &str = "
+        in [address]
+        jnz [address], #listen
+        out #1
+        out #10
+        out #20
+listen: in [x]
+        eq [x], #-1, [empty]
+        jnz [empty], #listen
+        in [y]
+        add [y], #1, [y]
+        out #255
+        out [x]
+        out [y]
+        jz #0, #listen
+address: db 0
+x:      db 0
+y:      db 0
+empty:  db 0
+";
+
+    fn packets<R: Router, N: Nat>(network: Network<R, N>, count: usize) -> Vec<Event> {
+        network
+            .filter(|event| !matches!(event, Event::NodeIdle(_) | Event::NetworkIdle))
+            .take(count)
+            .collect()
+    }
+
+    #[test]
+    fn the_nat_wakes_node_zero() {
+        let node = assemble(NODE).unwrap();
+        let router = AddressRouter {
+            nodes: 2,
+            nat: Some(255),
+        };
+        let network = Network::boot(
+            vec![node.clone(), node],
+            router,
+            LastPacketNat::default(),
+            None,
+        );
+        let packet = |source, destination, y| Packet {
+            source,
+            destination,
+            x: 10,
+            y,
+        };
+
+        assert_eq!(
+            packets(network, 5),
+            vec![
+                Event::PacketSent {
+                    packet: packet(0, 1, 20),
+                    route: Route::Node(1)
+                },
+                Event::PacketSent {
+                    packet: packet(1, 255, 21),
+                    route: Route::Nat
+                },
+                Event::NatDelivery(packet(1, 0, 21)),
+                Event::PacketSent {
+                    packet: packet(0, 255, 22),
+                    route: Route::Nat
+                },
+                Event::NatDelivery(packet(0, 0, 22)),
+            ]
+        );
+    }
+
+    #[test]
+    fn without_a_nat_the_network_settles() {
+        let node = assemble(NODE).unwrap();
+        let router = AddressRouter {
+            nodes: 3,
+            nat: None,
+        };
+        let network = Network::boot(
+            vec![node.clone(), node, vec![99]],
+            router,
+            LastPacketNat::default(),
+            None,
+        );
+        let mut events = network.collect::<Vec<_>>();
+
+        assert_eq!(events.pop(), Some(Event::NetworkIdle));
+        assert!(events.contains(&Event::NodeStopped {
+            node: 2,
+            status: CompStatus::Halted
+        }));
+        assert!(events.contains(&Event::PacketSent {
+            packet: Packet {
+                source: 1,
+                destination: 255,
+                x: 10,
+                y: 21
+            },
+            route: Route::Drop
+        }));
+    }
+}
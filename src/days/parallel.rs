@@ -0,0 +1,168 @@
+use std::cell::{Cell, RefCell};
+use std::panic;
+use std::sync::{mpsc, Mutex, Once};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::answer::Answer;
+use super::bench::format_duration;
+use super::Day;
+
+pub struct Outcome {
+    pub day: &'static Day,
+    pub part: u32,
+    // Why the star failed, a panic included
+    pub result: Result<Answer, String>,
+    pub elapsed: Duration,
+}
+
+thread_local! {
+    // Set while a star runs on a worker, its panic is kept for the report instead of being printed
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+    static PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+static HOOK: Once = Once::new();
+
+// Panics outside of a star still go to the previous hook
+fn install_hook() {
+    HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !CATCHING.with(Cell::get) {
+                return previous(info);
+            }
+
+            let payload = info.payload();
+            let message = match payload.downcast_ref::<&str>() {
+                Some(message) => message.to_string(),
+                None => match payload.downcast_ref::<String>() {
+                    Some(message) => message.clone(),
+                    None => "unknown payload".to_string(),
+                },
+            };
+            let location = match info.location() {
+                Some(location) => format!(" at {}:{}", location.file(), location.line()),
+                None => String::new(),
+            };
+            PANIC.with(|panic| {
+                *panic.borrow_mut() = Some(format!("panicked{}: {}", location, message))
+            });
+        }));
+    });
+}
+
+fn solve(day: &'static Day, part: u32) -> Outcome {
+    CATCHING.with(|catching| catching.set(true));
+    let now = Instant::now();
+    let result = panic::catch_unwind(|| day.solve(part).map_err(|error| error.to_string()));
+    let elapsed = now.elapsed();
+    CATCHING.with(|catching| catching.set(false));
+
+    Outcome {
+        day,
+        part,
+        result: result.unwrap_or_else(|_| {
+            Err(PANIC
+                .with(|panic| panic.borrow_mut().take())
+                .unwrap_or_else(|| "panicked".to_string()))
+        }),
+        elapsed,
+    }
+}
+
+// Solves the stars on `jobs` threads, each one taking the next star once it is done with its own.
+// The outcomes are sorted by day then part, whatever order they finished in.
+pub fn solve_all(stars: &[(&'static Day, u32)], jobs: usize) -> Vec<Outcome> {
+    install_hook();
+
+    let queue = Mutex::new(stars.iter());
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.max(1) {
+            let sender = sender.clone();
+            let queue = &queue;
+            scope.spawn(move || {
+                while let Some(&(day, part)) = queue.lock().unwrap().next() {
+                    sender.send(solve(day, part)).unwrap();
+                }
+            });
+        }
+    });
+    drop(sender);
+
+    let mut outcomes: Vec<Outcome> = receiver.into_iter().collect();
+    outcomes.sort_by_key(|outcome| (outcome.day.number, outcome.part));
+    outcomes
+}
+
+// `elapsed` is the wall time of the whole run, shorter than the sum of the stars' own times
+pub fn summary(outcomes: &[Outcome], elapsed: Duration, jobs: usize) -> String {
+    let failed = outcomes
+        .iter()
+        .filter(|outcome| outcome.result.is_err())
+        .count();
+    let unsolved = outcomes
+        .iter()
+        .filter(|outcome| outcome.result == Ok(Answer::Unsolved))
+        .count();
+
+    format!(
+        "Stars solved: {}, failed: {}, not solved yet: {}. Wall time: {}, threads: {}",
+        outcomes.len() - failed - unsolved,
+        failed,
+        unsolved,
+        format_duration(elapsed),
+        jobs.max(1)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static STARS: &[Day] = &[
+        Day {
+            number: 2,
+            title: "Panicking",
+            inputs: &[],
+            first_star: || Ok(Answer::Number(2)),
+            second_star: Some(|| panic!("no input")),
+        },
+        Day {
+            number: 1,
+            title: "Failing",
+            inputs: &[],
+            first_star: || Err("wrong input".into()),
+            second_star: None,
+        },
+    ];
+
+    #[test]
+    fn outcomes_are_sorted_and_panics_reported() {
+        let stars: Vec<(&'static Day, u32)> = STARS
+            .iter()
+            .flat_map(|day| vec![(day, 2), (day, 1)])
+            .collect();
+        let outcomes = solve_all(&stars, 3);
+
+        let order: Vec<(u32, u32)> = outcomes
+            .iter()
+            .map(|outcome| (outcome.day.number, outcome.part))
+            .collect();
+        assert_eq!(order, vec![(1, 1), (1, 2), (2, 1), (2, 2)]);
+
+        assert_eq!(outcomes[0].result, Err("wrong input".to_string()));
+        assert_eq!(outcomes[1].result, Ok(Answer::Unsolved));
+        assert_eq!(outcomes[2].result, Ok(Answer::Number(2)));
+        let panic = outcomes[3].result.clone().unwrap_err();
+        assert!(panic.starts_with("panicked at src/days/parallel.rs:"));
+        assert!(panic.ends_with(": no input"));
+
+        assert_eq!(
+            summary(&outcomes, Duration::from_millis(5), 3),
+            "Stars solved: 1, failed: 2, not solved yet: 1. Wall time: 5.000ms, threads: 3"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use itertools::Itertools;
+use std::collections::VecDeque;
+use std::error::Error;
+
+use super::intcode::{CompStatus, Intcode};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Topology {
+    // The signal goes through every stage once
+    Chain,
+    // The last stage feeds the first one until they all halt
+    Ring,
+}
+
+// Every stage gets its phase as first input, then the first stage gets the signal
+// and each stage passes everything it outputs to the next one
+#[derive(Clone, Debug)]
+pub struct Pipeline {
+    topology: Topology,
+    stages: Vec<(Vec<i64>, i64)>,
+}
+
+impl Pipeline {
+    pub fn new(topology: Topology) -> Self {
+        Pipeline {
+            topology,
+            stages: vec![],
+        }
+    }
+
+    pub fn stage(mut self, memory: Vec<i64>, phase: i64) -> Self {
+        self.stages.push((memory, phase));
+        self
+    }
+
+    // Returns the last value output by the last stage
+    pub fn run(&self, signal: i64) -> Result<i64, Box<dyn Error + 'static>> {
+        let mut stages = self
+            .stages
+            .iter()
+            .map(|(memory, phase)| Intcode::new(memory.clone()).add_input(*phase))
+            .collect::<VecDeque<_>>();
+
+        let mut signals = vec![signal];
+        let mut result = None;
+
+        let count = stages.len();
+        loop {
+            let mut moved = false;
+
+            for index in 0..count {
+                let mut stage = stages.pop_front().unwrap();
+                for signal in signals.drain(..) {
+                    stage.add_input_borrowing(signal);
+                }
+                let mut stage = stage.run();
+
+                if let CompStatus::Error(error) = stage.status {
+                    return Err(Box::new(error));
+                }
+                signals = stage.get_outputs();
+                moved |= !signals.is_empty();
+
+                if index == count - 1 {
+                    result = signals.last().cloned().or(result);
+                }
+                stages.push_back(stage);
+            }
+
+            let halted = stages
+                .iter()
+                .all(|stage| stage.status == CompStatus::Halted);
+            if self.topology == Topology::Chain || halted {
+                break;
+            }
+            if !moved {
+                return Err("Every stage of the ring waits for an input".into());
+            }
+        }
+
+        result.ok_or_else(|| "The last stage didn't output anything".into())
+    }
+}
+
+// Runs the same program in every stage for each ordering of the phases,
+// returns the ordering giving the highest signal, with that signal
+pub fn best_phases(
+    memory: &[i64],
+    phases: &[i64],
+    topology: Topology,
+) -> Result<(Vec<i64>, i64), Box<dyn Error + 'static>> {
+    let mut best: Option<(Vec<i64>, i64)> = None;
+
+    for permutation in phases.iter().cloned().permutations(phases.len()) {
+        let pipeline = permutation
+            .iter()
+            .fold(Pipeline::new(topology), |pipeline, phase| {
+                pipeline.stage(memory.to_vec(), *phase)
+            });
+        let signal = pipeline.run(0)?;
+
+        match &best {
+            Some((_, best_signal)) if *best_signal >= signal => {}
+            _ => best = Some((permutation, signal)),
+        }
+    }
+
+    best.ok_or_else(|| "No phase to try".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days::assembler::assemble;
+
+    // Adds its phase to each signal, three times
+    const ADDER: &str = "
+        in [phase]
+loop:   in [signal]
+        add [signal], [phase], [signal]
+        out [signal]
+        add [rounds], #-1, [rounds]
+        jnz [rounds], #loop
+        hlt
+phase:  db 0
+signal: db 0
+rounds: db 3
+";
+
+    // Outputs the first of every two signals it reads
+    const HALVER: &str = "
+        in [phase]
+loop:   in [first]
+        in [second]
+        out [first]
+        jz #0, #loop
+phase:  db 0
+first:  db 0
+second: db 0
+";
+
+    fn pipeline(topology: Topology, source: &str, phases: &[i64]) -> Pipeline {
+        let memory = assemble(source).unwrap();
+        phases
+            .iter()
+            .fold(Pipeline::new(topology), |pipeline, phase| {
+                pipeline.stage(memory.clone(), *phase)
+            })
+    }
+
+    #[test]
+    fn rings_feed_back_until_every_stage_halts() {
+        assert_eq!(pipeline(Topology::Chain, ADDER, &[1, 2]).run(0).unwrap(), 3);
+        assert_eq!(pipeline(Topology::Ring, ADDER, &[1, 2]).run(0).unwrap(), 9);
+        assert_eq!(pipeline(Topology::Ring, ADDER, &[5]).run(1).unwrap(), 16);
+    }
+
+    #[test]
+    fn stuck_rings_and_faults_are_errors() {
+        assert_eq!(
+            pipeline(Topology::Ring, HALVER, &[0, 0])
+                .run(1)
+                .unwrap_err()
+                .to_string(),
+            "Every stage of the ring waits for an input"
+        );
+        assert_eq!(
+            pipeline(Topology::Chain, HALVER, &[0])
+                .run(1)
+                .unwrap_err()
+                .to_string(),
+            "The last stage didn't output anything"
+        );
+        assert_eq!(
+            Pipeline::new(Topology::Chain)
+                .stage(vec![3, 0, 42], 0)
+                .run(0)
+                .unwrap_err()
+                .to_string(),
+            "unknown opcode 42 at address 2"
+        );
+    }
+
+    #[test]
+    fn best_phases_tries_every_order() {
+        // Multiplies the signal by 10 and adds the phase
+        let memory = assemble(
+            "
+        in [phase]
+        in [signal]
+        mul [signal], #10, [signal]
+        add [signal], [phase], [signal]
+        out [signal]
+        hlt
+phase:  db 0
+signal: db 0
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            best_phases(&memory, &[1, 3, 2], Topology::Chain).unwrap(),
+            (vec![3, 2, 1], 321)
+        );
+    }
+}
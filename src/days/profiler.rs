@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use super::disassembler;
+use super::intcode::{Opcode, ParameterMode};
+
+// Collected by an Intcode machine once profiling is enabled, the trace is only kept on request
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    pub steps: u64,
+    pub opcode_counts: HashMap<Opcode, u64>,
+    pub address_hits: HashMap<usize, u64>,
+    pub trace: Option<Vec<String>>,
+}
+
+impl Profile {
+    pub fn new(trace: bool) -> Self {
+        Profile {
+            trace: if trace { Some(vec![]) } else { None },
+            ..Default::default()
+        }
+    }
+
+    pub fn record(&mut self, address: usize, opcode: Opcode) {
+        self.steps += 1;
+        *self.opcode_counts.entry(opcode).or_insert(0) += 1;
+        *self.address_hits.entry(address).or_insert(0) += 1;
+    }
+
+    fn percent(&self, count: u64) -> f64 {
+        if self.steps == 0 {
+            0.0
+        } else {
+            count as f64 * 100.0 / self.steps as f64
+        }
+    }
+
+    // Lines are sorted by count then by opcode or address so two runs can be diffed as text
+    pub fn report(&self, memory: &[i64], top: usize) -> String {
+        let mut lines = vec![format!("Executed instructions: {}", self.steps)];
+
+        lines.push("Per opcode:".to_string());
+        let mut opcodes = self.opcode_counts.iter().collect::<Vec<_>>();
+        opcodes.sort_by(|a, b| b.1.cmp(a.1).then(a.0.code().cmp(&b.0.code())));
+        for (opcode, count) in opcodes {
+            lines.push(format!(
+                "  {:<4}{:>12} {:>6.2}%",
+                opcode.mnemonic(),
+                count,
+                self.percent(*count)
+            ));
+        }
+
+        lines.push(format!("Hottest {} addresses:", top));
+        let mut addresses = self.address_hits.iter().collect::<Vec<_>>();
+        addresses.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (address, count) in addresses.into_iter().take(top) {
+            let instruction = disassembler::decode_at(memory, *address)
+                .map(|instruction| instruction.to_string())
+                .unwrap_or_else(|| "?".to_string());
+            lines.push(format!(
+                "  {:04}: {:<32}{:>12} {:>6.2}%",
+                address,
+                instruction,
+                count,
+                self.percent(*count)
+            ));
+        }
+
+        lines.join("\n")
+    }
+}
+
+// Reading for the trace must not grow the memory like the machine does, out of range cells are 0
+fn peek(memory: &[i64], address: i64) -> Option<i64> {
+    if address < 0 {
+        None
+    } else {
+        Some(memory.get(address as usize).cloned().unwrap_or(0))
+    }
+}
+
+// Describes the instruction about to run with the values it reads, as the machine decoded it.
+// Also returns where it will write so the caller can append the stored value afterwards.
+pub fn trace_line(
+    memory: &[i64],
+    index: usize,
+    relative_offset: i64,
+    opcode: Opcode,
+    modes: [ParameterMode; 3],
+) -> (String, Option<usize>) {
+    let count = opcode.parameter_count();
+    let mut parameters = vec![];
+    let mut reads = vec![];
+    let mut destination = None;
+
+    for (offset, mode) in modes.iter().take(count).enumerate() {
+        let value = peek(memory, (index + 1 + offset) as i64).unwrap_or(0);
+        parameters.push(disassembler::format_parameter(*mode, value));
+
+        let target = match mode {
+            ParameterMode::Position => Some(value),
+            ParameterMode::Relative => Some(value + relative_offset),
+            ParameterMode::Immediate => None,
+        };
+
+        if opcode.writes_memory() && offset == count - 1 {
+            destination = target.filter(|address| *address >= 0).map(|a| a as usize);
+        } else {
+            reads.push(match target {
+                Some(address) => peek(memory, address)
+                    .map(|read| read.to_string())
+                    .unwrap_or_else(|| "?".to_string()),
+                None => value.to_string(),
+            });
+        }
+    }
+
+    let mut line = format!("{:04}: {}", index, opcode.mnemonic());
+    if !parameters.is_empty() {
+        line = format!("{} {}", line, parameters.join(", "));
+    }
+    if !reads.is_empty() {
+        line = format!("{:<40}| {}", line, reads.join(", "));
+    }
+    (line, destination)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days::intcode::Intcode;
+
+    #[test]
+    fn counts_per_opcode_and_address() {
+        // Adds 1 to [12] until it is equal to 3
+        let mut machine = Intcode::new(vec![
+            1001, 12, 1, 12, 1008, 12, 3, 13, 1006, 13, 0, 99, 0, 0,
+        ]);
+        machine.enable_profiling(false);
+        let mut machine = machine.run();
+        let profile = machine.take_profile().unwrap();
+
+        assert_eq!(profile.steps, 10);
+        assert_eq!(profile.opcode_counts[&Opcode::Add], 3);
+        assert_eq!(profile.opcode_counts[&Opcode::Halt], 1);
+        assert_eq!(profile.address_hits[&8], 3);
+        assert!(profile.trace.is_none());
+        assert_eq!(
+            profile.report(machine.memory(), 2),
+            "\
+Executed instructions: 10
+Per opcode:
+  ADD            3  30.00%
+  JZ             3  30.00%
+  EQ             3  30.00%
+  HLT            1  10.00%
+Hottest 2 addresses:
+  0000: ADD [12], #1, [12]                         3  30.00%
+  0004: EQ [12], #3, [13]                          3  30.00%"
+        );
+    }
+
+    #[test]
+    fn waiting_inputs_are_not_counted() {
+        let mut machine = Intcode::new(vec![3, 5, 4, 5, 99, 0]);
+        machine.enable_profiling(true);
+        let mut machine = machine.run().add_input(42).run();
+        let profile = machine.take_profile().unwrap();
+
+        assert_eq!(profile.steps, 3);
+        assert_eq!(
+            profile.trace.unwrap(),
+            vec![
+                "0000: IN [5] -> [5] = 42",
+                "0002: OUT [5]                           | 42",
+                "0004: HLT"
+            ]
+        );
+    }
+
+    fn trace(memory: Vec<i64>, inputs: &[i64]) -> Vec<String> {
+        let mut machine = Intcode::new(memory);
+        machine.set_inputs(inputs);
+        machine.enable_profiling(true);
+        machine.run_borrowing();
+        machine.take_profile().unwrap().trace.unwrap()
+    }
+
+    #[test]
+    fn traces_follow_the_machine_decoding() {
+        // A mode digit above 2 is read as relative, like the machine does
+        assert_eq!(
+            trace(vec![304, 1, 99], &[]),
+            vec!["0000: OUT [rb+1]                        | 1", "0002: HLT"]
+        );
+    }
+}
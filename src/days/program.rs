@@ -0,0 +1,122 @@
+use std::error::Error;
+use std::fmt;
+
+// Intcode programs as the puzzles give them: integers separated by commas. Spaces and line breaks
+// around them don't matter, a line may end with a comma and a `;` starts a comment:
+//   1,0,0,3,    ; add the noun and the verb
+//   99
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgramError {
+    pub line: usize,
+    pub column: usize,
+    // Empty when there is nothing between two commas
+    pub token: String,
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.token.is_empty() {
+            write!(
+                f,
+                "line {}, column {}: missing value",
+                self.line, self.column
+            )
+        } else {
+            write!(
+                f,
+                "line {}, column {}: `{}` is not an integer",
+                self.line, self.column, self.token
+            )
+        }
+    }
+}
+
+impl Error for ProgramError {}
+
+// An address of the program and the value written there before it runs
+pub type Patch = (usize, i64);
+
+pub fn load(text: &str) -> Result<Vec<i64>, ProgramError> {
+    let mut memory = vec![];
+
+    for (line_no, line) in text.lines().enumerate() {
+        let code = line.split(';').next().unwrap();
+        let tokens: Vec<&str> = code.split(',').collect();
+        let mut column = 1;
+
+        for (index, token) in tokens.iter().enumerate() {
+            let value = token.trim();
+            let is_last = index == tokens.len() - 1;
+
+            if !(value.is_empty() && is_last) {
+                memory.push(value.parse().map_err(|_| ProgramError {
+                    line: line_no + 1,
+                    column: column + token.chars().count() - token.trim_start().chars().count(),
+                    token: value.to_string(),
+                })?);
+            }
+            // The comma after the token
+            column += token.chars().count() + 1;
+        }
+    }
+
+    Ok(memory)
+}
+
+// Addresses past the end of the program are allocated, filled with zeros
+pub fn patch(memory: &mut Vec<i64>, patches: &[Patch]) {
+    for &(address, value) in patches {
+        if address >= memory.len() {
+            memory.resize(address + 1, 0);
+        }
+        memory[address] = value;
+    }
+}
+
+pub fn load_patched(text: &str, patches: &[Patch]) -> Result<Vec<i64>, ProgramError> {
+    let mut memory = load(text)?;
+    patch(&mut memory, patches);
+    Ok(memory)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trailing_newlines_and_comments() {
+        let text = "; adds the noun and the verb\n1,0,0,3,  ; into 3\n\n99\n\n";
+        assert_eq!(load(text), Ok(vec![1, 0, 0, 3, 99]));
+        assert_eq!(load("1,-2, 3\n"), Ok(vec![1, -2, 3]));
+    }
+
+    #[test]
+    fn malformed_tokens_are_located() {
+        let error = load("1,2,3\n4, five ,6").unwrap_err();
+        assert_eq!(
+            error,
+            ProgramError {
+                line: 2,
+                column: 4,
+                token: "five".to_string(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 4: `five` is not an integer"
+        );
+
+        let error = load("1,,2").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 3));
+        assert_eq!(error.to_string(), "line 1, column 3: missing value");
+    }
+
+    #[test]
+    fn patches_overwrite_and_extend() {
+        assert_eq!(
+            load_patched("1,0,0,3,99", &[(1, 12), (2, 2), (6, 7)]),
+            Ok(vec![1, 12, 2, 3, 99, 0, 7])
+        );
+    }
+}
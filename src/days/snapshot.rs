@@ -0,0 +1,337 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use super::intcode::{CompStatus, Intcode, IntcodeError};
+
+// Bumped whenever the layout below changes, older files are refused instead of misread
+const VERSION: u32 = 1;
+const HEADER: &str = "intcode-snapshot";
+
+// Full state of a machine using the default queues, one `key value` per line:
+//   intcode-snapshot 1
+//   status waiting
+//   index 42
+//   relative_offset 0
+//   output 0
+//   inputs 1,2
+//   outputs
+//   memory 109,1,204,-1,99
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    pub memory: Vec<i64>,
+    pub index: usize,
+    pub relative_offset: i64,
+    pub inputs: Vec<i64>,
+    pub outputs: Vec<i64>,
+    pub output: i64,
+    pub status: CompStatus,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotError {
+    // None when the problem is a line that isn't there
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "snapshot line {}: {}", line, self.message),
+            None => write!(f, "snapshot: {}", self.message),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+fn join(values: &[i64]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn status_to_text(status: CompStatus) -> String {
+    match status {
+        CompStatus::Running => "running".to_string(),
+        CompStatus::Waiting => "waiting".to_string(),
+        CompStatus::Halted => "halted".to_string(),
+        CompStatus::OutOfBudget => "out-of-budget".to_string(),
+        CompStatus::Error(IntcodeError::UnknownOpcode { address, opcode }) => {
+            format!("error unknown-opcode {} {}", address, opcode)
+        }
+        CompStatus::Error(IntcodeError::ImmediateWrite {
+            address,
+            instruction,
+        }) => format!("error immediate-write {} {}", address, instruction),
+        CompStatus::Error(IntcodeError::NegativeAddress { address, target }) => {
+            format!("error negative-address {} {}", address, target)
+        }
+        CompStatus::Error(IntcodeError::OutputOutOfBounds { address, target }) => {
+            format!("error output-out-of-bounds {} {}", address, target)
+        }
+    }
+}
+
+fn status_from_text(text: &str) -> Option<CompStatus> {
+    let words = text.split_whitespace().collect::<Vec<_>>();
+    let status = match words.as_slice() {
+        ["running"] => CompStatus::Running,
+        ["waiting"] => CompStatus::Waiting,
+        ["halted"] => CompStatus::Halted,
+        ["out-of-budget"] => CompStatus::OutOfBudget,
+        ["error", kind, address, value] => {
+            let address = address.parse().ok()?;
+            CompStatus::Error(match *kind {
+                "unknown-opcode" => IntcodeError::UnknownOpcode {
+                    address,
+                    opcode: value.parse().ok()?,
+                },
+                "immediate-write" => IntcodeError::ImmediateWrite {
+                    address,
+                    instruction: value.parse().ok()?,
+                },
+                "negative-address" => IntcodeError::NegativeAddress {
+                    address,
+                    target: value.parse().ok()?,
+                },
+                "output-out-of-bounds" => IntcodeError::OutputOutOfBounds {
+                    address,
+                    target: value.parse().ok()?,
+                },
+                _ => return None,
+            })
+        }
+        _ => return None,
+    };
+    Some(status)
+}
+
+impl Snapshot {
+    pub fn to_text(&self) -> String {
+        vec![
+            format!("{} {}", HEADER, VERSION),
+            format!("status {}", status_to_text(self.status)),
+            format!("index {}", self.index),
+            format!("relative_offset {}", self.relative_offset),
+            format!("output {}", self.output),
+            format!("inputs {}", join(&self.inputs)),
+            format!("outputs {}", join(&self.outputs)),
+            format!("memory {}", join(&self.memory)),
+        ]
+        .into_iter()
+        .map(|line| line.trim_end().to_string() + "\n")
+        .collect()
+    }
+
+    pub fn parse(text: &str) -> Result<Snapshot, SnapshotError> {
+        let mut lines = text.lines().enumerate().map(|(no, line)| (no + 1, line));
+
+        match lines.next() {
+            Some((_, header)) if header.trim() == format!("{} {}", HEADER, VERSION) => {}
+            Some((no, header)) => {
+                return Err(SnapshotError {
+                    line: Some(no),
+                    message: format!(
+                        "expected `{} {}`, found `{}`",
+                        HEADER,
+                        VERSION,
+                        header.trim()
+                    ),
+                })
+            }
+            None => {
+                return Err(SnapshotError {
+                    line: None,
+                    message: "empty snapshot".to_string(),
+                })
+            }
+        }
+
+        let mut next = |key: &str| -> Result<(usize, String), SnapshotError> {
+            match lines.next() {
+                Some((no, line)) => {
+                    let line = line.trim();
+                    let (found, value) = match line.find(' ') {
+                        Some(split) => (&line[..split], line[split + 1..].trim()),
+                        None => (line, ""),
+                    };
+                    if found == key {
+                        Ok((no, value.to_string()))
+                    } else {
+                        Err(SnapshotError {
+                            line: Some(no),
+                            message: format!("expected `{}`, found `{}`", key, found),
+                        })
+                    }
+                }
+                None => Err(SnapshotError {
+                    line: None,
+                    message: format!("missing `{}`", key),
+                }),
+            }
+        };
+
+        fn number<T: std::str::FromStr>(line: usize, text: &str) -> Result<T, SnapshotError> {
+            text.parse().map_err(|_| SnapshotError {
+                line: Some(line),
+                message: format!("invalid number `{}`", text),
+            })
+        }
+
+        fn numbers(line: usize, text: &str) -> Result<Vec<i64>, SnapshotError> {
+            if text.is_empty() {
+                return Ok(vec![]);
+            }
+            text.split(',')
+                .map(|value| number(line, value.trim()))
+                .collect()
+        }
+
+        let (line, status) = next("status")?;
+        let status = status_from_text(&status).ok_or_else(|| SnapshotError {
+            line: Some(line),
+            message: format!("invalid status `{}`", status),
+        })?;
+        let (line, index) = next("index")?;
+        let index = number(line, &index)?;
+        let (line, relative_offset) = next("relative_offset")?;
+        let relative_offset = number(line, &relative_offset)?;
+        let (line, output) = next("output")?;
+        let output = number(line, &output)?;
+        let (line, inputs) = next("inputs")?;
+        let inputs = numbers(line, &inputs)?;
+        let (line, outputs) = next("outputs")?;
+        let outputs = numbers(line, &outputs)?;
+        let (line, memory) = next("memory")?;
+        let memory = numbers(line, &memory)?;
+
+        Ok(Snapshot {
+            memory,
+            index,
+            relative_offset,
+            inputs,
+            outputs,
+            output,
+            status,
+        })
+    }
+}
+
+// Errors name the file they are about
+pub fn save(machine: &Intcode, path: &Path) -> Result<(), Box<dyn Error + 'static>> {
+    fs::write(path, machine.snapshot().to_text())
+        .map_err(|error| format!("{}: {}", path.display(), error))?;
+    Ok(())
+}
+
+pub fn load(path: &Path) -> Result<Intcode, Box<dyn Error + 'static>> {
+    let text =
+        fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    let snapshot =
+        Snapshot::parse(&text).map_err(|error| format!("{}: {}", path.display(), error))?;
+    Ok(Intcode::from_snapshot(snapshot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn missing_keys_have_no_line() {
+        let error = Snapshot::parse("intcode-snapshot 1\nstatus halted\nindex 0\n").unwrap_err();
+
+        assert_eq!(error.line, None);
+        assert_eq!(error.to_string(), "snapshot: missing `relative_offset`");
+    }
+
+    #[test]
+    fn load_errors_name_the_file() {
+        let path = env::temp_dir().join(format!("aoc_2019_truncated_{}.txt", std::process::id()));
+        fs::write(&path, "intcode-snapshot 1\nstatus halted\n").unwrap();
+        let error = load(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            format!("{}: snapshot: missing `index`", path.display())
+        );
+    }
+
+    #[test]
+    fn a_restored_machine_carries_on() {
+        // Sums as many inputs as [18] says, with the relative base pointing at the input cell
+        let program = vec![
+            109, 20, 203, 0, 201, 0, 19, 19, 1001, 18, -1, 18, 1005, 18, 2, 4, 19, 99, 3, 0, 0,
+        ];
+        let machine = Intcode::new(program).add_input(1).add_input(2).run();
+        assert_eq!(machine.status, CompStatus::Waiting);
+
+        let text = machine.snapshot().to_text();
+        assert!(
+            text.starts_with("intcode-snapshot 1\nstatus waiting\nindex 2\nrelative_offset 20\n")
+        );
+        let snapshot = Snapshot::parse(&text).unwrap();
+        assert_eq!(snapshot, machine.snapshot());
+
+        let mut restored = Intcode::from_snapshot(snapshot).add_input(4).run();
+        let mut original = machine.add_input(4).run();
+        assert_eq!(restored.get_outputs(), vec![7]);
+        assert_eq!(original.get_outputs(), vec![7]);
+    }
+
+    #[test]
+    fn faults_survive_a_round_trip() {
+        let machine = Intcode::new(vec![104, 5, 1, -1, 0, 0, 99]).run();
+        let snapshot = Snapshot::parse(&machine.snapshot().to_text()).unwrap();
+
+        assert_eq!(
+            snapshot.status,
+            CompStatus::Error(IntcodeError::NegativeAddress {
+                address: 2,
+                target: -1
+            })
+        );
+        assert_eq!(snapshot.outputs, vec![5]);
+    }
+
+    #[test]
+    fn corrupt_snapshots_are_refused() {
+        let valid = Intcode::new(vec![99]).snapshot().to_text();
+        let corrupt = |from: &str, to: &str| {
+            Snapshot::parse(&valid.replace(from, to))
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            corrupt("snapshot 1", "snapshot 2"),
+            "snapshot line 1: expected `intcode-snapshot 1`, found `intcode-snapshot 2`"
+        );
+        assert_eq!(
+            corrupt("status running", "status sleeping"),
+            "snapshot line 2: invalid status `sleeping`"
+        );
+        assert_eq!(
+            corrupt("index 0", "index -1"),
+            "snapshot line 3: invalid number `-1`"
+        );
+        assert_eq!(
+            corrupt("memory 99", "memory 99,x"),
+            "snapshot line 8: invalid number `x`"
+        );
+        assert_eq!(
+            corrupt("output 0\ninputs", "inputs"),
+            "snapshot line 5: expected `output`, found `inputs`"
+        );
+        assert_eq!(
+            Snapshot::parse("").unwrap_err().to_string(),
+            "snapshot: empty snapshot"
+        );
+    }
+}
@@ -0,0 +1,425 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+use super::intcode::{InputSource, Intcode, OutputSink};
+
+// Machines fed a default value never block, they count as idle after this many empty reads in a row
+const IDLE_POLLS: u32 = 2;
+
+#[derive(Debug)]
+struct Participant {
+    empty_reads: u32,
+    idle_after: u32,
+    retired: bool,
+}
+
+impl Participant {
+    fn is_idle(&self) -> bool {
+        self.retired || self.empty_reads >= self.idle_after
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    // One per inbox, indexed by the inbox id
+    participants: Vec<Participant>,
+    // Values sent through any port of the monitor and not received yet
+    in_flight: usize,
+    // Inboxes that became idle, (id, retired), only kept once report_idle has been called
+    idle_reports: Option<VecDeque<(usize, bool)>>,
+}
+
+impl State {
+    fn is_quiescent(&self) -> bool {
+        self.in_flight == 0 && self.participants.iter().all(Participant::is_idle)
+    }
+
+    // Returns true when this read made the participant idle
+    fn empty_read(&mut self, id: usize) -> bool {
+        let participant = &mut self.participants[id];
+        let was_idle = participant.is_idle();
+        participant.empty_reads = participant.empty_reads.saturating_add(1);
+        let became_idle = !was_idle && participant.is_idle();
+
+        if let (true, Some(reports)) = (became_idle, self.idle_reports.as_mut()) {
+            reports.push_back((id, false));
+        }
+        became_idle
+    }
+}
+
+// Shared by every channel of a topology, it knows whether anything can still happen:
+// the network is quiescent once nothing is in flight and every inbox owner waits on an empty queue
+#[derive(Debug, Default)]
+pub struct Monitor {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl Monitor {
+    pub fn new() -> Arc<Monitor> {
+        Arc::new(Monitor::default())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    // From now on recv_or_quiescent also tells when an inbox owner goes idle or retires
+    pub fn report_idle(&self) {
+        self.lock().idle_reports.get_or_insert_with(VecDeque::new);
+    }
+
+    pub fn channel<T>(self: &Arc<Self>) -> (Port<T>, Inbox<T>) {
+        let (sender, receiver) = mpsc::channel();
+        let mut state = self.lock();
+        let id = state.participants.len();
+        state.participants.push(Participant {
+            empty_reads: 0,
+            idle_after: 1,
+            retired: false,
+        });
+
+        let port = Port {
+            sender: Some(sender),
+            monitor: self.clone(),
+            target: id,
+            owner: None,
+        };
+        let inbox = Inbox {
+            receiver,
+            monitor: self.clone(),
+            id,
+            default: None,
+        };
+        (port, inbox)
+    }
+}
+
+#[derive(Debug)]
+pub struct Port<T> {
+    // Only None while dropping, see below
+    sender: Option<Sender<T>>,
+    monitor: Arc<Monitor>,
+    target: usize,
+    owner: Option<usize>,
+}
+
+impl<T> Clone for Port<T> {
+    fn clone(&self) -> Self {
+        Port {
+            sender: self.sender.clone(),
+            monitor: self.monitor.clone(),
+            target: self.target,
+            owner: self.owner,
+        }
+    }
+}
+
+impl<T> Port<T> {
+    // Sending proves the owner is busy, a machine sending through this port is never taken for idle
+    pub fn owned_by<U>(mut self, inbox: &Inbox<U>) -> Self {
+        self.owner = Some(inbox.id);
+        self
+    }
+
+    pub fn send(&self, value: T) -> bool {
+        self.send_all(vec![value])
+    }
+
+    // The values are queued at once, the receiver can't see only the first ones.
+    // Returns false when nobody reads the inbox anymore, the values are then dropped.
+    pub fn send_all<V: IntoIterator<Item = T>>(&self, values: V) -> bool {
+        let mut state = self.monitor.lock();
+        if let Some(owner) = self.owner {
+            state.participants[owner].empty_reads = 0;
+        }
+
+        let mut delivered = !state.participants[self.target].retired;
+        if delivered {
+            for value in values {
+                match self.sender.as_ref().map(|sender| sender.send(value)) {
+                    Some(Ok(())) => state.in_flight += 1,
+                    _ => {
+                        delivered = false;
+                        break;
+                    }
+                }
+            }
+        }
+
+        drop(state);
+        self.monitor.changed.notify_all();
+        delivered
+    }
+}
+
+// The sender is dropped while holding the lock, so a receiver can't check for a closed channel,
+// miss the drop and then wait for a notification that already happened
+impl<T> Drop for Port<T> {
+    fn drop(&mut self) {
+        let state = self.monitor.lock();
+        self.sender.take();
+        drop(state);
+        self.monitor.changed.notify_all();
+    }
+}
+
+impl OutputSink for Port<i64> {
+    fn push_output(&mut self, value: i64) {
+        self.send(value);
+    }
+}
+
+// Output sink for many machines sharing one inbox, every value comes with the tag of its sender
+#[derive(Debug, Clone)]
+pub struct Tagged {
+    pub tag: usize,
+    port: Port<(usize, i64)>,
+}
+
+impl Port<(usize, i64)> {
+    pub fn tagged(self, tag: usize) -> Tagged {
+        Tagged { tag, port: self }
+    }
+}
+
+impl OutputSink for Tagged {
+    fn push_output(&mut self, value: i64) {
+        self.port.send((self.tag, value));
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Received<T> {
+    Value(T),
+    // Only with Monitor::report_idle, the id is the one of the inbox whose owner went idle
+    Idle { inbox: usize, retired: bool },
+    Quiescent,
+    Closed,
+}
+
+#[derive(Debug)]
+pub struct Inbox<T> {
+    receiver: Receiver<T>,
+    monitor: Arc<Monitor>,
+    id: usize,
+    default: Option<T>,
+}
+
+impl<T: Clone> Inbox<T> {
+    // Reading an empty inbox returns `value` instead of blocking, like day23's -1
+    pub fn with_default(mut self, value: T) -> Self {
+        self.default = Some(value);
+        self.monitor.lock().participants[self.id].idle_after = IDLE_POLLS;
+        self
+    }
+
+    fn receive(&self, stop_when_quiescent: bool) -> Received<T> {
+        let mut state = self.monitor.lock();
+        loop {
+            match self.receiver.try_recv() {
+                Ok(value) => {
+                    state.in_flight -= 1;
+                    state.participants[self.id].empty_reads = 0;
+                    drop(state);
+                    self.monitor.changed.notify_all();
+                    return Received::Value(value);
+                }
+                Err(TryRecvError::Disconnected) => return Received::Closed,
+                Err(TryRecvError::Empty) => {
+                    let was_idle = state.participants[self.id].is_idle();
+                    let became_idle = state.empty_read(self.id);
+
+                    if let Some(default) = &self.default {
+                        drop(state);
+                        // Only becoming idle matters to the others, an idle poller just gives way
+                        if became_idle {
+                            self.monitor.changed.notify_all();
+                        } else if was_idle {
+                            thread::yield_now();
+                        }
+                        return Received::Value(default.clone());
+                    }
+                    if stop_when_quiescent {
+                        let report = state.idle_reports.as_mut().and_then(VecDeque::pop_front);
+                        if let Some((inbox, retired)) = report {
+                            return Received::Idle { inbox, retired };
+                        }
+                        if state.is_quiescent() {
+                            return Received::Quiescent;
+                        }
+                    }
+
+                    if became_idle {
+                        self.monitor.changed.notify_all();
+                    }
+                    state = self.monitor.changed.wait(state).unwrap();
+                }
+            }
+        }
+    }
+
+    // Blocks for the next value, None once every port is gone
+    pub fn recv(&self) -> Option<T> {
+        match self.receive(false) {
+            Received::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    // Same as recv but also gives up once the whole network is quiescent, this inbox being empty included
+    pub fn recv_or_quiescent(&self) -> Received<T> {
+        self.receive(true)
+    }
+}
+
+impl<T> Inbox<T> {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    // The owner won't read anymore: it no longer holds back quiescence and later values are dropped
+    pub fn retire(&self) {
+        let mut state = self.monitor.lock();
+        let participant = &mut state.participants[self.id];
+        if participant.retired {
+            return;
+        }
+        participant.retired = true;
+        if let Some(reports) = state.idle_reports.as_mut() {
+            reports.push_back((self.id, true));
+        }
+        while self.receiver.try_recv().is_ok() {
+            state.in_flight -= 1;
+        }
+        drop(state);
+        self.monitor.changed.notify_all();
+    }
+}
+
+impl<T> Drop for Inbox<T> {
+    fn drop(&mut self) {
+        self.retire();
+    }
+}
+
+impl InputSource for Inbox<i64> {
+    fn next_input(&mut self) -> Option<i64> {
+        self.recv()
+    }
+}
+
+// Runs the machine on its own thread until it halts, fails or its inbox is closed, then hands it back
+pub fn spawn<O: OutputSink + Send + 'static>(
+    machine: Intcode<Inbox<i64>, O>,
+) -> JoinHandle<Intcode<Inbox<i64>, O>> {
+    thread::spawn(move || {
+        let machine = machine.run();
+        machine.inputs().retire();
+        machine
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days::intcode::CompStatus;
+
+    // Echoes every input it reads
+    fn echo<O: OutputSink>(inbox: Inbox<i64>, output: O) -> Intcode<Inbox<i64>, O> {
+        Intcode::with_io(vec![3, 7, 4, 7, 1105, 1, 0, 0], inbox, output)
+    }
+
+    #[test]
+    fn a_ring_becomes_quiescent() {
+        let monitor = Monitor::new();
+        let (to_first, first) = monitor.channel();
+        let (to_second, second) = monitor.channel();
+        let (to_main, main) = monitor.channel();
+
+        let to_second = to_second.owned_by(&first);
+        let to_main = to_main.owned_by(&second);
+        let first = spawn(echo(first, to_second));
+        let second = spawn(echo(second, to_main));
+
+        to_first.send_all(vec![4, 2]);
+        assert_eq!(main.recv_or_quiescent(), Received::Value(4));
+        assert_eq!(main.recv_or_quiescent(), Received::Value(2));
+        assert_eq!(main.recv_or_quiescent(), Received::Quiescent);
+
+        // Closing the first inbox stops the first machine, whose port to the second one goes with it
+        drop(to_first);
+        drop(first.join().unwrap());
+        assert_eq!(second.join().unwrap().status, CompStatus::Waiting);
+        assert_eq!(main.recv_or_quiescent(), Received::Closed);
+    }
+
+    #[test]
+    fn tagged_outputs_share_an_inbox() {
+        let monitor = Monitor::new();
+        let (to_main, main) = monitor.channel();
+        let machines = (0..3)
+            .map(|tag| {
+                let (port, inbox) = monitor.channel();
+                port.send(tag as i64 * 10);
+                drop(port);
+                spawn(echo(inbox, to_main.clone().tagged(tag)))
+            })
+            .collect::<Vec<_>>();
+        drop(to_main);
+        // Each machine holds its port until it is joined
+        for machine in machines {
+            machine.join().unwrap();
+        }
+
+        let mut received = vec![];
+        while let Some(value) = main.recv() {
+            received.push(value);
+        }
+        received.sort();
+        assert_eq!(received, vec![(0, 0), (1, 10), (2, 20)]);
+    }
+
+    #[test]
+    fn idle_reports_and_retired_inboxes() {
+        let monitor = Monitor::new();
+        monitor.report_idle();
+        let (to_main, main) = monitor.channel::<i64>();
+        let (to_polling, polling) = monitor.channel();
+        let polling = polling.with_default(-1);
+
+        assert_eq!(polling.recv(), Some(-1));
+        assert_eq!(polling.recv(), Some(-1));
+        assert_eq!(
+            main.recv_or_quiescent(),
+            Received::Idle {
+                inbox: polling.id(),
+                retired: false
+            }
+        );
+
+        // The reader waiting on its own empty inbox is idle too
+        assert_eq!(
+            main.recv_or_quiescent(),
+            Received::Idle {
+                inbox: main.id(),
+                retired: false
+            }
+        );
+
+        polling.retire();
+        assert!(!to_polling.send(3));
+        assert_eq!(
+            main.recv_or_quiescent(),
+            Received::Idle {
+                inbox: polling.id(),
+                retired: true
+            }
+        );
+        assert_eq!(main.recv_or_quiescent(), Received::Quiescent);
+        drop(to_main);
+    }
+}
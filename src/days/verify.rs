@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+use super::answer::Answer;
+use super::bench::format_duration;
+use super::Day;
+
+// Name of the answers file for input::read_input, next to the puzzle inputs
+pub const ANSWERS: &str = "answers";
+
+pub type Expected = HashMap<(u32, u32), String>;
+
+// One answer per line: `<day> <part> <answer>`, blank lines and lines starting with # are skipped.
+// Images are written on a single line, with \n between the rows.
+pub fn parse_answers(text: &str) -> Result<Expected, Box<dyn Error + 'static>> {
+    let mut expected = HashMap::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let entry = line
+            .split_once(char::is_whitespace)
+            .and_then(|(day, rest)| {
+                let (part, answer) = rest.trim_start().split_once(char::is_whitespace)?;
+                Some((day.parse().ok()?, part.parse().ok()?, answer.trim()))
+            });
+        match entry {
+            Some((day, part, answer)) => {
+                expected.insert((day, part), answer.to_string());
+            }
+            None => {
+                return Err(format!(
+                    "Line {} of the answers: expected `<day> <part> <answer>`, got `{}`",
+                    index + 1,
+                    line
+                )
+                .into())
+            }
+        }
+    }
+
+    Ok(expected)
+}
+
+fn flatten(answer: &Answer) -> String {
+    answer.to_string().replace('\n', "\\n").trim().to_string()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Verdict {
+    Pass,
+    Fail { expected: String },
+    // Nothing to compare with, the answer is only shown
+    Unchecked,
+    // Never a pass, and a failure when an answer is expected for the star
+    Unsolved { expected: Option<String> },
+    Error,
+}
+
+#[derive(Clone, Debug)]
+pub struct Check {
+    pub day: u32,
+    pub part: u32,
+    // The answer given by the star, or its error
+    pub answer: String,
+    pub verdict: Verdict,
+    pub time: Duration,
+}
+
+impl Check {
+    pub fn failed(&self) -> bool {
+        matches!(
+            self.verdict,
+            Verdict::Fail { .. } | Verdict::Unsolved { expected: Some(_) } | Verdict::Error
+        )
+    }
+}
+
+pub fn check(day: &Day, part: u32, expected: &Expected) -> Check {
+    let now = Instant::now();
+    let result = day.solve(part);
+    let time = now.elapsed();
+
+    let (answer, verdict) = match result {
+        Err(error) => (error.to_string(), Verdict::Error),
+        Ok(Answer::Unsolved) => (
+            Answer::Unsolved.to_string(),
+            Verdict::Unsolved {
+                expected: expected.get(&(day.number, part)).cloned(),
+            },
+        ),
+        Ok(answer) => {
+            let answer = flatten(&answer);
+            let verdict = match expected.get(&(day.number, part)) {
+                None => Verdict::Unchecked,
+                Some(expected) if *expected == answer => Verdict::Pass,
+                Some(expected) => Verdict::Fail {
+                    expected: expected.clone(),
+                },
+            };
+            (answer, verdict)
+        }
+    };
+
+    Check {
+        day: day.number,
+        part,
+        answer,
+        verdict,
+        time,
+    }
+}
+
+pub fn table(checks: &[Check]) -> String {
+    let mut lines = vec![format!(
+        "{:>3} {:>4}  {:<9} {:>10}  Answer",
+        "Day", "Part", "Status", "Time"
+    )];
+
+    for check in checks {
+        let (status, answer) = match &check.verdict {
+            Verdict::Pass => ("pass", check.answer.clone()),
+            Verdict::Fail { expected } => {
+                ("FAIL", format!("{}, expected {}", check.answer, expected))
+            }
+            Verdict::Unchecked => ("unchecked", check.answer.clone()),
+            Verdict::Unsolved { expected: None } => ("unsolved", check.answer.clone()),
+            Verdict::Unsolved {
+                expected: Some(expected),
+            } => (
+                "UNSOLVED",
+                format!("{}, expected {}", check.answer, expected),
+            ),
+            Verdict::Error => ("ERROR", check.answer.clone()),
+        };
+        lines.push(format!(
+            "{:>3} {:>4}  {:<9} {:>10}  {}",
+            check.day,
+            check.part,
+            status,
+            format_duration(check.time),
+            answer
+        ));
+    }
+
+    let passed = checks
+        .iter()
+        .filter(|check| check.verdict == Verdict::Pass)
+        .count();
+    let failed = checks.iter().filter(|check| check.failed()).count();
+    let unsolved = checks
+        .iter()
+        .filter(|check| check.verdict == Verdict::Unsolved { expected: None })
+        .count();
+    lines.push(format!(
+        "{} passed, {} failed, {} unsolved, {} unchecked",
+        passed,
+        failed,
+        unsolved,
+        checks.len() - passed - failed - unsolved
+    ));
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static DAY: Day = Day {
+        number: 8,
+        title: "Image",
+        inputs: &[],
+        first_star: || Ok(Answer::Number(1806)),
+        second_star: Some(|| Ok(Answer::Image("#..#\n#..#".to_string()))),
+    };
+
+    static BROKEN: Day = Day {
+        number: 9,
+        title: "Broken",
+        inputs: &[],
+        first_star: || Err("no input".into()),
+        second_star: None,
+    };
+
+    #[test]
+    fn answers_file() {
+        let expected =
+            parse_answers("# day part answer\n\n8 1 1806\n8  2   #..#\\n#..#\n").unwrap();
+
+        assert_eq!(expected.len(), 2);
+        assert_eq!(expected[&(8, 2)], "#..#\\n#..#");
+        assert_eq!(
+            parse_answers("8 1 1806\neight 2 3")
+                .unwrap_err()
+                .to_string(),
+            "Line 2 of the answers: expected `<day> <part> <answer>`, got `eight 2 3`"
+        );
+    }
+
+    #[test]
+    fn verdicts() {
+        let expected = parse_answers("8 1 1806\n8 2 #..#\\n#..#\n9 2 5").unwrap();
+        let mut wrong = expected.clone();
+        wrong.insert((8, 1), "1807".to_string());
+
+        assert_eq!(check(&DAY, 1, &expected).verdict, Verdict::Pass);
+        assert_eq!(check(&DAY, 2, &expected).verdict, Verdict::Pass);
+        assert_eq!(
+            check(&DAY, 1, &wrong).verdict,
+            Verdict::Fail {
+                expected: "1807".to_string()
+            }
+        );
+        assert_eq!(check(&BROKEN, 1, &expected).verdict, Verdict::Error);
+        // An unsolved star is only shown, unless an answer is expected for it
+        let unsolved = check(&BROKEN, 2, &Expected::new());
+        assert_eq!(unsolved.verdict, Verdict::Unsolved { expected: None });
+        assert_eq!(unsolved.answer, "not solved");
+        assert!(!unsolved.failed());
+        let unsolved = check(&BROKEN, 2, &expected);
+        assert_eq!(
+            unsolved.verdict,
+            Verdict::Unsolved {
+                expected: Some("5".to_string())
+            }
+        );
+        assert!(unsolved.failed());
+    }
+
+    #[test]
+    fn table_counts_the_verdicts() {
+        let expected = parse_answers("8 1 1807").unwrap();
+        let checks = vec![
+            check(&DAY, 1, &expected),
+            check(&DAY, 2, &expected),
+            check(&BROKEN, 1, &expected),
+            check(&BROKEN, 2, &expected),
+        ];
+        let table = table(&checks);
+        let lines = table.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines[0], "Day Part  Status          Time  Answer");
+        assert!(lines[1].starts_with("  8    1  FAIL "));
+        assert!(lines[1].ends_with("  1806, expected 1807"));
+        assert!(lines[2].ends_with("  #..#\\n#..#"));
+        assert!(lines[3].ends_with("  no input"));
+        assert!(lines[4].starts_with("  9    2  unsolved "));
+        assert!(lines[4].ends_with("  not solved"));
+        assert_eq!(lines[5], "0 passed, 2 failed, 1 unsolved, 1 unchecked");
+    }
+}
@@ -0,0 +1,8 @@
+// The Intcode machine, its tooling and every day's solvers. The binary in main.rs is only a
+// command line over this crate.
+pub mod days;
+
+pub use days::answer::Answer;
+pub use days::grid;
+pub use days::intcode::Intcode;
+pub use days::{find, Day, DAYS};
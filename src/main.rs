@@ -1,155 +1,455 @@
+use std::env;
 use std::error::Error;
+use std::fs;
 use std::io::{self};
-use std::time::Instant;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
 
-mod days;
+use aoc_2019::days;
+use aoc_2019::{Answer, Day};
+use days::bench::format_duration;
+use days::input::Inputs;
 
-type Star = fn() -> Result<(), Box<dyn Error + 'static>>;
+const USAGE: &str = "Usage:
+    aoc_2019 run <day> [--part 1|2] [--input <path>] [--input <name>=<path>] [--input-dir <dir>] [--quiet] [--format text|json]
+    aoc_2019 run --all [--part 1|2] [--input <name>=<path>] [--input-dir <dir>] [--quiet] [--format text|json] [--jobs <threads>]
+    aoc_2019 verify <day>|--all [--part 1|2] [--answers <path>] [input options]
+    aoc_2019 bench <day>|--all [--part 1|2] [--warmup <runs>] [--iterations <runs>] [input options]
+    aoc_2019 list
+    aoc_2019 play
+    aoc_2019 asm <file>
+    aoc_2019 disasm <file>
+    aoc_2019 debug <file>
+    aoc_2019 profile <file> [--trace] [--budget=<steps>] [inputs...]
+    aoc_2019 bench-intcode
 
-fn default_star() -> Result<(), Box<dyn Error + 'static>> {
-    Ok(())
+Inputs are read from <dir>/<name>.txt, the directory defaults to $AOC_INPUT_DIR then ./data.
+`aoc_2019 list` shows the input names of every day, a path of - reads stdin.
+A plain --input <path> is only accepted for days reading a single input.
+With --format json, every star is printed as one JSON object per line.
+`run --all` solves the days in parallel, one per thread and as many threads as cores unless --jobs is given.
+The expected answers are read from answers.txt in the same directory, one `<day> <part> <answer>` per line.";
+
+// Something went wrong while running: a star failed, a file could not be read...
+const EXIT_FAILURE: i32 = 1;
+// The command line itself is wrong
+const EXIT_USAGE: i32 = 2;
+
+// Returns false when the star failed
+fn report(day: &Day, part: u32, options: &RunOptions) -> bool {
+    let now = Instant::now();
+    let result = day.solve(part);
+    print_star(day, part, result, now.elapsed(), options)
 }
 
-fn main() {
-    let mut buffer = String::new();
-    let mut day: u32 = 1;
-    println!("Please enter the day number: ");
-    match io::stdin().read_line(&mut buffer) {
-        Ok(_) => {
-            // Must use trim_end or the line ending mess up with the parsing function
-            day = buffer.trim_end().parse().unwrap_or(1);
-            println!("Attempting to run day{}", day);
-        }
-        Err(error) => println!("Error: {}, defaulting to day1", error),
+fn print_star(
+    day: &Day,
+    part: u32,
+    result: Result<Answer, Box<dyn Error + 'static>>,
+    elapsed: Duration,
+    options: &RunOptions,
+) -> bool {
+    let name = if part == 1 { "First" } else { "Second" };
+
+    if options.format == Format::Json {
+        println!("{}", days::json::record(day.number, part, &result, elapsed));
+        return result.is_ok();
     }
-
-    let first_star: Star;
-    let second_star: Star;
-    match day {
-        1 => {
-            first_star = days::day1::first_star;
-            second_star = days::day1::second_star;
-        }
-        2 => {
-            first_star = days::day2::first_star;
-            second_star = days::day2::second_star;
-        }
-        3 => {
-            first_star = days::day3::first_star;
-            second_star = days::day3::second_star;
-        }
-        4 => {
-            first_star = days::day4::first_star;
-            second_star = days::day4::second_star;
-        }
-        5 => {
-            first_star = days::day5::first_star;
-            second_star = days::day5::second_star;
-        }
-        6 => {
-            first_star = days::day6::first_star;
-            second_star = days::day6::second_star;
-        }
-        7 => {
-            first_star = days::day7::first_star;
-            second_star = days::day7::second_star;
-        }
-        8 => {
-            first_star = days::day8::first_star;
-            second_star = days::day8::second_star;
-        }
-        9 => {
-            first_star = days::day9::first_star;
-            second_star = days::day9::second_star;
-        }
-        10 => {
-            first_star = days::day10::first_star;
-            second_star = days::day10::second_star;
-        }
-        11 => {
-            first_star = days::day11::first_star;
-            second_star = days::day11::second_star;
-        }
-        12 => {
-            first_star = days::day12::first_star;
-            second_star = days::day12::second_star;
-        }
-        13 => {
-            first_star = days::day13::first_star;
-            second_star = days::day13::second_star;
-        }
-        14 => {
-            first_star = days::day14::first_star;
-            second_star = days::day14::second_star;
+
+    let time = format_duration(elapsed);
+    match result {
+        Err(error) => {
+            eprintln!(
+                "Day {} {} Star: Error: {} ({})",
+                day.number, name, error, time
+            );
+            return false;
         }
-        15 => {
-            first_star = days::day15::first_star;
-            second_star = days::day15::second_star;
+        Ok(answer) if options.quiet => println!("{}", answer),
+        Ok(Answer::Unsolved) => println!("{} Star: not solved yet", name),
+        Ok(Answer::Image(image)) => println!("{} Star ({}):\n{}", name, time, image),
+        Ok(answer) => println!("{} Star: {} ({})", name, answer, time),
+    }
+    true
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Mode {
+    Run,
+    Verify,
+    Bench,
+}
+
+struct RunOptions {
+    days: Vec<&'static Day>,
+    part: Option<u32>,
+    inputs: Inputs,
+    // Only the answers are printed, one per line
+    quiet: bool,
+    format: Format,
+    // Untimed runs of each star before the measured ones
+    warmup: usize,
+    iterations: usize,
+    // Threads solving the days of `run --all`
+    jobs: usize,
+}
+
+impl RunOptions {
+    fn parts(&self) -> Vec<u32> {
+        match self.part {
+            Some(part) => vec![part],
+            None => vec![1, 2],
         }
-        16 => {
-            first_star = days::day16::first_star;
-            second_star = days::day16::second_star;
+    }
+}
+
+fn parse_count(value: Option<&String>, option: &str) -> Result<usize, String> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| format!("{} expects a number", option))
+}
+
+fn parse_run(args: &[String], mode: Mode) -> Result<RunOptions, String> {
+    let mut options = RunOptions {
+        days: vec![],
+        part: None,
+        inputs: Inputs::default(),
+        quiet: false,
+        format: Format::Text,
+        warmup: 0,
+        iterations: 1,
+        jobs: thread::available_parallelism().map_or(1, |cores| cores.get()),
+    };
+    let mut all = false;
+    let mut jobs = false;
+    let mut answers = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--all" => all = true,
+            "--quiet" if mode == Mode::Run => options.quiet = true,
+            "--format" if mode == Mode::Run => match args.next().map(String::as_str) {
+                Some("text") => options.format = Format::Text,
+                Some("json") => options.format = Format::Json,
+                _ => return Err("--format expects text or json".to_string()),
+            },
+            "--jobs" if mode == Mode::Run => {
+                options.jobs = parse_count(args.next(), "--jobs")?.max(1);
+                jobs = true;
+            }
+            "--warmup" if mode == Mode::Bench => {
+                options.warmup = parse_count(args.next(), "--warmup")?
+            }
+            "--iterations" if mode == Mode::Bench => {
+                options.iterations = parse_count(args.next(), "--iterations")?.max(1)
+            }
+            "--answers" if mode == Mode::Verify => match args.next() {
+                Some(path) => answers = Some(path.as_str().into()),
+                None => return Err("--answers expects a path".to_string()),
+            },
+            "--part" => match args.next().map(String::as_str) {
+                Some("1") => options.part = Some(1),
+                Some("2") => options.part = Some(2),
+                _ => return Err("--part expects 1 or 2".to_string()),
+            },
+            "--input" => match args.next() {
+                Some(input) => match input.split_once('=') {
+                    Some((name, path)) => {
+                        options.inputs.by_name.insert(name.to_string(), path.into());
+                    }
+                    None => options.inputs.all = Some(input.as_str().into()),
+                },
+                None => return Err("--input expects a path".to_string()),
+            },
+            "--input-dir" => match args.next() {
+                Some(dir) => options.inputs.dir = Some(PathBuf::from(dir)),
+                None => return Err("--input-dir expects a directory".to_string()),
+            },
+            option if option.starts_with("--") => {
+                return Err(format!("Unknown option: {}", option))
+            }
+            _ if !options.days.is_empty() => {
+                return Err("Only one day can be run, use --all to run them all".to_string())
+            }
+            day => match day.parse().ok().and_then(days::find) {
+                Some(day) => options.days.push(day),
+                None => return Err(format!("There is no day {}", day)),
+            },
         }
-        17 => {
-            first_star = days::day17::first_star;
-            second_star = days::day17::second_star;
+    }
+
+    match (all, options.days.is_empty()) {
+        (true, false) => return Err("Either give a day or --all, not both".to_string()),
+        (true, true) if options.inputs.all.is_some() => {
+            return Err("--input <path> can only be given for a single day".to_string())
         }
-        18 => {
-            first_star = days::day18::first_star;
-            second_star = days::day18::second_star;
+        (true, true) => options.days = days::DAYS.iter().collect(),
+        (false, true) => return Err("Which day should run?".to_string()),
+        (false, false) if jobs => return Err("--jobs can only be given with --all".to_string()),
+        (false, false) => {}
+    }
+
+    // Day 18 reads a different map for each part, one path can't stand for both
+    if let (Some(_), [day]) = (&options.inputs.all, options.days.as_slice()) {
+        if day.inputs.len() > 1 {
+            return Err(format!(
+                "Day {} reads several inputs, give each one with --input <name>=<path>: {}",
+                day.number,
+                day.inputs.join(", ")
+            ));
         }
-        19 => {
-            first_star = days::day19::first_star;
-            second_star = days::day19::second_star;
+    }
+    for name in options.inputs.by_name.keys() {
+        if !options
+            .days
+            .iter()
+            .any(|day| day.inputs.contains(&name.as_str()))
+        {
+            return Err(format!("No day to run reads an input called {}", name));
         }
-        20 => {
-            first_star = days::day20::first_star;
-            second_star = days::day20::second_star;
+    }
+    if options.quiet && options.format == Format::Json {
+        return Err("--quiet only applies to the text format".to_string());
+    }
+    if let Some(answers) = answers {
+        options
+            .inputs
+            .by_name
+            .insert(days::verify::ANSWERS.to_string(), answers);
+    }
+
+    Ok(options)
+}
+
+fn run(options: &RunOptions) -> i32 {
+    days::input::configure(options.inputs.clone());
+    if options.days.len() > 1 {
+        return run_parallel(options);
+    }
+
+    let mut code = 0;
+    for day in &options.days {
+        if !options.quiet && options.format == Format::Text {
+            println!("Day {}: {}", day.number, day.title);
         }
-        21 => {
-            first_star = days::day21::first_star;
-            second_star = days::day21::second_star;
+
+        for part in options.parts() {
+            if !report(day, part, options) {
+                code = EXIT_FAILURE;
+            }
         }
-        22 => {
-            first_star = days::day22::first_star;
-            second_star = days::day22::second_star;
+    }
+
+    code
+}
+
+// Nothing is printed before every star is done, then the stars are reported in order.
+// A star that panics is reported as failed, the other ones still run.
+fn run_parallel(options: &RunOptions) -> i32 {
+    let stars: Vec<(&'static Day, u32)> = options
+        .days
+        .iter()
+        .flat_map(|day| options.parts().into_iter().map(move |part| (*day, part)))
+        .collect();
+
+    let now = Instant::now();
+    let outcomes = days::parallel::solve_all(&stars, options.jobs);
+    let elapsed = now.elapsed();
+
+    let verbose = !options.quiet && options.format == Format::Text;
+    let mut code = 0;
+    for (index, outcome) in outcomes.iter().enumerate() {
+        if verbose && (index == 0 || outcomes[index - 1].day.number != outcome.day.number) {
+            println!("Day {}: {}", outcome.day.number, outcome.day.title);
         }
-        23 => {
-            first_star = days::day23::first_star;
-            second_star = days::day23::second_star;
+
+        let result = outcome.result.clone().map_err(|error| error.into());
+        if !print_star(outcome.day, outcome.part, result, outcome.elapsed, options) {
+            code = EXIT_FAILURE;
         }
-        24 => {
-            first_star = days::day24::first_star;
-            second_star = days::day24::second_star;
+    }
+
+    if verbose {
+        println!(
+            "{}",
+            days::parallel::summary(&outcomes, elapsed, options.jobs)
+        );
+    }
+
+    code
+}
+
+fn verify(options: &RunOptions) -> Result<bool, Box<dyn Error + 'static>> {
+    days::input::configure(options.inputs.clone());
+    let expected = days::verify::parse_answers(&days::input::read_input(days::verify::ANSWERS)?)?;
+
+    let mut checks = vec![];
+    for day in &options.days {
+        for part in options.parts() {
+            checks.push(days::verify::check(day, part, &expected));
         }
-        25 => {
-            first_star = days::finalday::first_star;
-            second_star = days::finalday::second_star;
+    }
+
+    println!("{}", days::verify::table(&checks));
+    Ok(checks.iter().all(|check| !check.failed()))
+}
+
+fn bench(options: &RunOptions) -> bool {
+    days::input::configure(options.inputs.clone());
+
+    let mut timings = vec![];
+    for day in &options.days {
+        for part in options.parts() {
+            timings.push(days::bench::measure(
+                day,
+                part,
+                options.warmup,
+                options.iterations,
+            ));
         }
-        _ => {
-            println!("Executing nothing");
-            first_star = default_star;
-            second_star = default_star;
+    }
+
+    println!("{}", days::bench::table(&timings));
+    timings.iter().all(|timing| !timing.failed())
+}
+
+fn list() {
+    for day in days::DAYS {
+        let inputs = match day.inputs {
+            [] => "no input".to_string(),
+            inputs => inputs.join(", "),
+        };
+        println!("{:>2} {} ({})", day.number, day.title, inputs);
+    }
+}
+
+fn read(path: &str) -> Result<String, Box<dyn Error + 'static>> {
+    fs::read_to_string(path).map_err(|error| format!("{}, could not read {}", error, path).into())
+}
+
+fn load(path: &str) -> Result<Vec<i64>, Box<dyn Error + 'static>> {
+    days::program::load(&read(path)?).map_err(|error| format!("{}: {}", path, error).into())
+}
+
+fn disassemble(path: &str) -> Result<(), Box<dyn Error + 'static>> {
+    print!("{}", days::disassembler::disassemble(&load(path)?));
+    Ok(())
+}
+
+fn assemble(path: &str) -> Result<(), Box<dyn Error + 'static>> {
+    let memory = days::assembler::assemble(&read(path)?)?;
+    println!(
+        "{}",
+        memory
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+    Ok(())
+}
+
+fn debug(path: &str) -> Result<(), Box<dyn Error + 'static>> {
+    let machine = days::intcode::Intcode::new(load(path)?);
+    let mut debugger = days::debugger::Debugger::new(machine);
+    let stdin = io::stdin();
+    debugger.repl(stdin.lock(), io::stdout())?;
+    Ok(())
+}
+
+fn profile(path: &str, options: &[String]) -> Result<(), Box<dyn Error + 'static>> {
+    let trace = options.iter().any(|option| option == "--trace");
+    let budget = options
+        .iter()
+        .find_map(|option| option.strip_prefix("--budget="))
+        .map(|budget| budget.parse::<u64>())
+        .transpose()
+        .map_err(|error| format!("{}, the budget must be a number of steps", error))?;
+    let inputs = options
+        .iter()
+        .filter(|option| !option.starts_with("--"))
+        .map(|input| input.parse::<i64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| format!("{}, inputs must be integers", error))?;
+
+    let mut machine = days::intcode::Intcode::new(load(path)?);
+    machine.set_inputs(&inputs);
+    machine.enable_profiling(trace);
+    machine.set_budget(budget);
+    let mut machine = machine.run();
+
+    if let Some(profile) = machine.take_profile() {
+        if let Some(trace) = &profile.trace {
+            for line in trace {
+                println!("{}", line);
+            }
         }
+        println!("{}", profile.report(machine.memory(), 20));
     }
+    let outputs = machine.get_outputs();
+    println!("Status: {:?}, outputs: {:?}", machine.status, outputs);
+    Ok(())
+}
 
-    let now = Instant::now();
-    match first_star() {
-        Err(x) => {
-            println!("Error: {:?}", x);
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let result = match (args.get(1).map(String::as_str), args.len()) {
+        (Some(command @ ("run" | "verify" | "bench")), _) => {
+            let mode = match command {
+                "run" => Mode::Run,
+                "verify" => Mode::Verify,
+                _ => Mode::Bench,
+            };
+            let options = match parse_run(&args[2..], mode) {
+                Ok(options) => options,
+                Err(error) => {
+                    eprintln!("Error: {}\n{}", error, USAGE);
+                    process::exit(EXIT_USAGE);
+                }
+            };
+
+            match mode {
+                Mode::Run => process::exit(run(&options)),
+                Mode::Verify => match verify(&options) {
+                    Ok(true) => Ok(()),
+                    Ok(false) => process::exit(EXIT_FAILURE),
+                    Err(error) => Err(error),
+                },
+                Mode::Bench if bench(&options) => Ok(()),
+                Mode::Bench => process::exit(EXIT_FAILURE),
+            }
         }
-        _ => {
-            println!("First Star: Success!");
+        (Some("list"), 2) => {
+            list();
+            Ok(())
         }
-    }
-    match second_star() {
-        Err(x) => {
-            println!("Error {:?}", x);
+        (Some("play"), 2) => days::finalday::play(),
+        (Some("asm"), 3) => assemble(&args[2]),
+        (Some("disasm"), 3) => disassemble(&args[2]),
+        (Some("debug"), 3) => debug(&args[2]),
+        (Some("profile"), len) if len >= 3 => profile(&args[2], &args[3..]),
+        (Some("bench-intcode"), 2) => {
+            days::intcode_bench::run();
+            Ok(())
         }
         _ => {
-            println!("Second Star: Success!");
+            eprintln!("{}", USAGE);
+            process::exit(EXIT_USAGE);
         }
+    };
+
+    if let Err(error) = result {
+        eprintln!("Error: {}", error);
+        process::exit(EXIT_FAILURE);
     }
-    let end = now.elapsed();
-    println!("{}.{}", end.as_secs(), end.subsec_millis());
 }
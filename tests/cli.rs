@@ -0,0 +1,132 @@
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn binary() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_aoc_2019"))
+}
+
+// Runs the binary with the puzzle input given on stdin
+fn run(args: &[&str], input: &str) -> Output {
+    run_command(binary().args(args), input)
+}
+
+fn run_command(command: &mut Command, input: &str) -> Output {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+const ORBITS: &str = "COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\nK)YOU\nI)SAN\n";
+
+#[test]
+fn run_reads_the_input_from_stdin() {
+    let output = run(&["run", "1", "--input", "-"], "12\n14\n1969\n100756\n");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(stdout.contains("First Star: 34241"));
+    assert!(stdout.contains("Second Star: 51316"));
+}
+
+#[test]
+fn json_lines_give_one_record_per_star() {
+    let output = run(&["run", "6", "--input", "-", "--format", "json"], ORBITS);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    assert!(output.status.success());
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with(r#"{"day":6,"part":1,"status":"solved","answer":54,"#));
+    assert!(lines[1].starts_with(r#"{"day":6,"part":2,"status":"solved","answer":4,"#));
+}
+
+#[test]
+fn a_missing_input_fails_the_run() {
+    let output = run(&["run", "1", "--input", "no/such/input"], "");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("Could not read no/such/input"));
+}
+
+#[test]
+fn unknown_days_are_rejected() {
+    let output = run(&["run", "26"], "");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("There is no day 26"));
+}
+
+#[test]
+fn verify_reads_the_answers_apart_from_the_input() {
+    let dir = env::temp_dir().join(format!("aoc_2019_verify_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("answers.txt"), "1 1 34241\n1 2 51316\n").unwrap();
+    let input = dir.join("d1.txt");
+    fs::write(&input, "12\n14\n1969\n100756\n").unwrap();
+
+    let output = run(
+        &[
+            "verify",
+            "1",
+            "--input-dir",
+            dir.to_str().unwrap(),
+            "--input",
+            input.to_str().unwrap(),
+        ],
+        "",
+    );
+    fs::remove_dir_all(&dir).unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(stdout.contains("2 passed, 0 failed, 0 unsolved, 0 unchecked"));
+}
+
+#[test]
+fn play_stops_at_the_end_of_stdin() {
+    let dir = env::temp_dir().join(format!("aoc_2019_play_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    // Prints `?` then reads commands forever
+    fs::write(dir.join("finalday.txt"), "104,63,104,10,3,100,1105,1,4").unwrap();
+
+    let output = run_command(binary().arg("play").env("AOC_INPUT_DIR", &dir), "north\n");
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().starts_with("?\n"));
+}
+
+#[test]
+fn jobs_need_every_day() {
+    let output = run(&["run", "1", "--jobs", "2"], "");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("--jobs can only be given with --all"));
+}
+
+#[test]
+fn one_input_path_is_refused_for_several_inputs() {
+    let output = run(&["run", "18", "--input", "-"], "");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().contains(
+        "Day 18 reads several inputs, give each one with --input <name>=<path>: day18_a, day18_b"
+    ));
+}
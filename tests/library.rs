@@ -0,0 +1,68 @@
+use aoc_2019::days::{day18, day22, program};
+use aoc_2019::{find, grid, Answer, Intcode};
+
+#[test]
+fn the_machine_runs_any_program() {
+    // Outputs 1 when the input is equal to 8, 0 otherwise
+    let program = program::load("3,9,8,9,10,9,4,9,99,-1,8").unwrap();
+
+    assert_eq!(
+        Intcode::new(program.clone())
+            .add_input(8)
+            .run()
+            .get_outputs(),
+        vec![1]
+    );
+    assert_eq!(
+        Intcode::new(program).add_input(7).run().get_outputs(),
+        vec![0]
+    );
+}
+
+#[test]
+fn modular_inverse() {
+    let (gcd, _, inverse) = day22::extended_euclid(10_007, 3);
+
+    assert_eq!(gcd, 1);
+    assert_eq!((3 * inverse).rem_euclid(10_007), 1);
+    assert_eq!(day22::modular_power(3, 10_006, 10_007), 1);
+}
+
+#[test]
+fn grid_distances() {
+    // A ring of open cells around (1, 1)
+    let distances = grid::distances((0, 0), |(x, y)| {
+        (0..3).contains(&x) && (0..3).contains(&y) && (x, y) != (1, 1)
+    });
+
+    assert_eq!(distances.len(), 8);
+    assert_eq!(distances[&(2, 2)], 4);
+    assert!(!distances.contains_key(&(1, 1)));
+}
+
+#[test]
+fn key_graph_of_a_small_vault() {
+    let graph = day18::key_graph("#########\n#b.A.@.a#\n#########");
+
+    assert_eq!(graph[&'a'].lockers.len(), 0);
+    assert!(graph[&'b'].lockers.contains(&'a'));
+    assert_eq!(day18::shortest_path("#########\n#b.A.@.a#\n#########"), 8);
+}
+
+#[test]
+fn days_are_found_by_number() {
+    let day = find(4).unwrap();
+
+    assert_eq!(day.title, "Secure Container");
+    assert!(find(26).is_none());
+    assert_eq!(Answer::from(42i64).to_string(), "42");
+}
+
+#[test]
+fn the_last_day_has_no_second_puzzle() {
+    let day = find(25).unwrap();
+
+    assert!(day.second_star.is_none());
+    assert_eq!(day.solve(2).unwrap(), Answer::Unsolved);
+    assert!(day.solve(3).is_err());
+}